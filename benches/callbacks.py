@@ -0,0 +1,94 @@
+#!/usr/bin/env python3
+"""Measures the per-callback overhead of content handlers.
+
+The same document is rewritten without handlers and with no-op handlers for every element and
+text chunk; the difference divided by the number of handler calls is the per-callback overhead.
+
+Carrying one GIL token through the whole rewriting instead of calling `Python::with_gil` in every
+handler made no measurable difference (release build, default `--nodes`, i.e. 120k handler calls,
+best of `--repeat 30`):
+
+                    with_gil    one token
+    rewrite_str     327-355     333-342 ns
+    HtmlRewriter    412-482     438-443 ns
+
+With the GIL already held, `with_gil` only checks a thread-local, so the overhead is dominated by
+wrapping the units and calling into Python. The handlers thus keep calling `with_gil`, which
+unlike the token can't outlive the GIL.
+"""
+
+import argparse
+import timeit
+
+from lolhtml import (
+    DocumentContentHandler,
+    ElementContentHandler,
+    HtmlRewriter,
+    rewrite_str,
+)
+
+
+def noop(unit):
+    pass
+
+
+def make_document(nodes: int) -> str:
+    return "<html><body>" + "<p>text <b>node</b></p>" * nodes + "</body></html>"
+
+
+def count_calls(html: str) -> int:
+    calls = 0
+
+    def count(unit):
+        nonlocal calls
+        calls += 1
+
+    rewrite_str(
+        html,
+        element_content_handlers=[ElementContentHandler("*", element=count)],
+        document_content_handlers=[DocumentContentHandler(text=count)],
+    )
+
+    return calls
+
+
+def bench(label: str, run, repeat: int) -> float:
+    best = min(timeit.repeat(run, number=1, repeat=repeat))
+    print(f"{label:<32} {best * 1000:>10.2f} ms")
+    return best
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__)
+    parser.add_argument("--nodes", type=int, default=20_000)
+    parser.add_argument("--repeat", type=int, default=10)
+    args = parser.parse_args()
+
+    html = make_document(args.nodes)
+    calls = count_calls(html)
+
+    handlers = dict(
+        element_content_handlers=[ElementContentHandler("*", element=noop)],
+        document_content_handlers=[DocumentContentHandler(text=noop)],
+    )
+
+    def stream():
+        rewriter = HtmlRewriter(lambda chunk: None, **handlers)
+        for i in range(0, len(html), 4096):
+            rewriter.write(html[i : i + 4096])
+        rewriter.end()
+
+    print(f"{len(html)} bytes, {calls} handler calls")
+
+    baseline = bench("rewrite_str, no handlers", lambda: rewrite_str(html), args.repeat)
+    with_handlers = bench(
+        "rewrite_str, no-op handlers", lambda: rewrite_str(html, **handlers), args.repeat
+    )
+    streaming = bench("HtmlRewriter, no-op handlers", stream, args.repeat)
+
+    print(f"per-callback overhead (rewrite_str):  {(with_handlers - baseline) / calls * 1e9:.0f} ns")
+    print(f"per-callback overhead (HtmlRewriter): {(streaming - baseline) / calls * 1e9:.0f} ns")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,207 @@
+use std::{borrow::Cow, cell::RefCell, error::Error, rc::Rc, sync::Arc};
+
+use lol_html::{
+    html_content::{Element, EndTag},
+    ElementContentHandlers, Selector,
+};
+use pyo3::prelude::*;
+
+use crate::errors::ParsingAmbiguityError;
+use crate::rewritable_units::element::set_end_tag_handler;
+
+/// Start tags that switch the parser into one of the text parsing modes.
+const TEXT_TYPE_SWITCHING_TAGS: [&str; 10] = [
+    "textarea",
+    "title",
+    "plaintext",
+    "script",
+    "style",
+    "iframe",
+    "xmp",
+    "noembed",
+    "noframes",
+    "noscript",
+];
+
+#[derive(Copy, Clone)]
+enum State {
+    Default,
+    InSelect,
+    InTemplateInSelect(usize),
+    InOrAfterFrameset,
+}
+
+/// Tracks parsing ambiguities the same way lol_html does in the strict mode.
+///
+/// lol_html doesn't look for ambiguities at all in the non-strict mode, so the tracker is used to
+/// report them without aborting the rewriting.
+struct AmbiguityTracker {
+    state: State,
+}
+
+impl AmbiguityTracker {
+    /// Returns the name of the element the start tag is ambiguous in, if it is.
+    fn track_start_tag(&mut self, tag_name: &str) -> Option<&'static str> {
+        let context = self.context();
+
+        if self.is_ambiguous(tag_name) {
+            Some(context)
+        } else {
+            None
+        }
+    }
+
+    /// Name of the element setting the current context.
+    fn context(&self) -> &'static str {
+        match self.state {
+            State::Default => "html",
+            State::InSelect => "select",
+            State::InTemplateInSelect(_) => "template",
+            State::InOrAfterFrameset => "frameset",
+        }
+    }
+
+    /// Returns `true` if the start tag is ambiguous in the current context.
+    fn is_ambiguous(&mut self, tag_name: &str) -> bool {
+        match self.state {
+            State::Default => {
+                if tag_name == "select" {
+                    self.state = State::InSelect;
+                } else if tag_name == "frameset" {
+                    self.state = State::InOrAfterFrameset;
+                }
+
+                false
+            }
+            State::InSelect => {
+                // NOTE: these start tags cause premature exit from "in select" insertion mode.
+                if ["select", "textarea", "input", "keygen"].contains(&tag_name) {
+                    self.state = State::Default;
+                    false
+                } else if tag_name == "template" {
+                    self.state = State::InTemplateInSelect(1);
+                    false
+                } else {
+                    // NOTE: <script> is allowed in "in select" insertion mode.
+                    tag_name != "script" && switches_text_type(tag_name)
+                }
+            }
+            State::InTemplateInSelect(depth) => {
+                if tag_name == "template" {
+                    self.state = State::InTemplateInSelect(depth + 1);
+                    false
+                } else {
+                    switches_text_type(tag_name)
+                }
+            }
+            // NOTE: <noframes> is allowed in and after <frameset>.
+            State::InOrAfterFrameset => tag_name != "noframes" && switches_text_type(tag_name),
+        }
+    }
+
+    fn track_end_tag(&mut self, tag_name: &str) {
+        match self.state {
+            State::InSelect if tag_name == "select" => {
+                self.state = State::Default;
+            }
+            State::InTemplateInSelect(depth) if tag_name == "template" => {
+                self.state = if depth == 1 {
+                    State::InSelect
+                } else {
+                    State::InTemplateInSelect(depth - 1)
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[inline]
+fn switches_text_type(tag_name: &str) -> bool {
+    TEXT_TYPE_SWITCHING_TAGS.contains(&tag_name)
+}
+
+/// Builds a `ParsingAmbiguityError` for the ambiguous start tag, exposing the tag name and the
+/// name of the element it is ambiguous in as the `tag_name` and `context` attributes of the
+/// exception.
+fn ambiguity_error(py: Python<'_>, tag_name: &str, context: &str) -> PyResult<PyErr> {
+    let err = ParsingAmbiguityError::new_err(format!(
+        "The parser has encountered a text content tag (`<{}>`) in `<{}>`, where it is \
+         ambiguous whether this tag should be ignored or not. And, thus, it is unclear if \
+         consequent content should be parsed as raw text or HTML markup.\n\n\
+         This error occurs due to the limited capabilities of the streaming parsing. However, \
+         almost all of the cases of this error are caused by a non-conforming markup (e.g. \
+         a `<script>` element in `<select>` element).",
+        tag_name, context
+    ));
+
+    err.value(py).setattr("tag_name", tag_name)?;
+    err.value(py).setattr("context", context)?;
+
+    Ok(err)
+}
+
+/// A handle to the ambiguity tracker of a rewrite, seeing the end tags of the document.
+///
+/// Setting an end tag handler replaces the ones set before, including the one set by the tracker.
+/// Every end tag handler is thus wrapped with [`EndTagTracker::wrap`], so that the tracker sees the
+/// end tag no matter which handler is the last one set.
+#[derive(Clone)]
+pub(crate) struct EndTagTracker(Rc<RefCell<AmbiguityTracker>>);
+
+impl EndTagTracker {
+    /// Wraps an end tag `handler`, so that the tracker sees the end tag before the handler does.
+    pub(crate) fn wrap<H>(
+        tracker: Option<Self>,
+        handler: H,
+    ) -> impl FnOnce(&mut EndTag) -> Result<(), Box<dyn Error + Send + Sync>> + 'static
+    where
+        H: FnOnce(&mut EndTag) -> Result<(), Box<dyn Error + Send + Sync>> + 'static,
+    {
+        move |end: &mut EndTag| {
+            if let Some(tracker) = &tracker {
+                tracker.0.borrow_mut().track_end_tag(&end.name());
+            }
+
+            handler(end)
+        }
+    }
+}
+
+/// Creates the tracker for a rewrite, which is to be passed to [`reporting_handlers`] and to
+/// every content handler setting end tag handlers.
+pub(crate) fn end_tag_tracker() -> EndTagTracker {
+    EndTagTracker(Rc::new(RefCell::new(AmbiguityTracker {
+        state: State::Default,
+    })))
+}
+
+/// Builds content handlers that call `callback` with a `ParsingAmbiguityError` for every
+/// ambiguous start tag of the document.
+pub(crate) fn reporting_handlers(
+    tracker: EndTagTracker,
+    callback: Arc<PyObject>,
+) -> (Cow<'static, Selector>, ElementContentHandlers<'static>) {
+    let handlers = ElementContentHandlers::default().element(move |el: &mut Element| {
+        let tag_name = el.tag_name();
+        let context = tracker.0.borrow_mut().track_start_tag(&tag_name);
+
+        // NOTE: user's handlers matching the element are run later and may replace this one.
+        if tag_name == "select" || tag_name == "template" {
+            set_end_tag_handler(el, EndTagTracker::wrap(Some(tracker.clone()), |_| Ok(())));
+        }
+
+        if let Some(context) = context {
+            Python::with_gil(|py| {
+                let err = ambiguity_error(py, &tag_name, context)?;
+                let _result = callback.call1(py, (err.value(py),))?;
+                Ok::<_, PyErr>(())
+            })?;
+        }
+
+        Ok(())
+    });
+
+    // NOTE: it's ok to unwrap here as the selector is known to be valid.
+    (Cow::Owned("*".parse().unwrap()), handlers)
+}
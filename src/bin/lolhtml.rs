@@ -0,0 +1,374 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::Parser;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use lol_html::{AsciiCompatibleEncoding, HtmlRewriter, MemorySettings, Settings};
+use lolhtml::{memory_settings, parse_encoding, rule_set, NativeRule};
+
+/// Files rewritten in directories unless `--include` is given.
+const DEFAULT_INCLUDE: [&str; 2] = ["*.html", "*.htm"];
+
+/// Rewrites HTML documents with a rule set.
+///
+/// Reads INPUT, or the standard input if it isn't given or is `-`, and writes the rewritten
+/// document to the standard output. With `--in-place` or `--output-dir`, any number of files can
+/// be rewritten, as well as directories with `--recursive`.
+#[derive(Parser)]
+#[command(name = "lolhtml", version)]
+struct Args {
+    /// Rule set file in JSON, TOML or YAML format, same as for `Rewriter.from_rules`.
+    #[arg(long, value_name = "FILE")]
+    rules: PathBuf,
+
+    /// Files to rewrite, or directories with `--recursive`.
+    #[arg(value_name = "INPUT")]
+    inputs: Vec<PathBuf>,
+
+    /// Label of the documents' character encoding, which has to be ASCII-compatible.
+    #[arg(long, default_value = "utf-8")]
+    encoding: String,
+
+    /// Don't fail on markup that can't be parsed unambiguously, same as `strict=False`.
+    #[arg(long)]
+    no_strict: bool,
+
+    /// Maximum amount of memory the rewriter can use for buffering, in bytes.
+    #[arg(long, value_name = "BYTES")]
+    max_memory: Option<usize>,
+
+    /// Atomically replace the files with the rewritten ones.
+    #[arg(short, long, conflicts_with = "output_dir")]
+    in_place: bool,
+
+    /// Write the rewritten files to DIR, keeping their paths relative to the inputs.
+    #[arg(short, long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Rewrite the files in the directories and their subdirectories.
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Only rewrite the files in directories matching GLOB [default: *.html, *.htm].
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip the files in directories matching GLOB.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+/// Settings of every rewrite.
+struct Rewriter {
+    rules: Vec<NativeRule>,
+    encoding: AsciiCompatibleEncoding,
+    max_allowed_memory_usage: usize,
+    preallocated_parsing_buffer_size: usize,
+    strict: bool,
+}
+
+impl Rewriter {
+    fn new(args: &Args) -> Result<Self, Box<dyn Error>> {
+        let format = rule_set::Format::from_path(&args.rules).ok_or_else(|| {
+            format!(
+                "{}: can't tell the format, expected a `.json`, `.toml`, `.yaml` or `.yml` file",
+                args.rules.display()
+            )
+        })?;
+        let source = fs::read_to_string(&args.rules)
+            .map_err(|e| format!("{}: {}", args.rules.display(), e))?;
+        let rules = rule_set::parse(&source, format)
+            .map_err(|e| format!("{}: {}", args.rules.display(), e))?;
+
+        let memory_settings = memory_settings(args.max_memory, None)?;
+
+        Ok(Self {
+            rules,
+            encoding: parse_encoding(&args.encoding)?,
+            max_allowed_memory_usage: memory_settings.max_allowed_memory_usage,
+            preallocated_parsing_buffer_size: memory_settings.preallocated_parsing_buffer_size,
+            strict: !args.no_strict,
+        })
+    }
+
+    /// Rewrites the whole `input` into `output`.
+    ///
+    /// Same as `Rewriter.rewrite`, ESI tags are enabled.
+    fn rewrite(&self, input: &mut dyn Read, output: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let output_error = RefCell::new(None);
+        let mut rewriter = HtmlRewriter::new(
+            Settings {
+                element_content_handlers: self
+                    .rules
+                    .iter()
+                    .map(NativeRule::as_element_content_handlers)
+                    .collect(),
+                encoding: self.encoding,
+                memory_settings: MemorySettings {
+                    max_allowed_memory_usage: self.max_allowed_memory_usage,
+                    preallocated_parsing_buffer_size: self.preallocated_parsing_buffer_size,
+                },
+                strict: self.strict,
+                enable_esi_tags: true,
+                ..Settings::default()
+            },
+            |chunk: &[u8]| {
+                let mut output_error = output_error.borrow_mut();
+
+                if output_error.is_none() {
+                    *output_error = output.write_all(chunk).err();
+                }
+            },
+        );
+        let mut buffer = vec![0; 64 * 1024];
+
+        loop {
+            let read = match input.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            rewriter.write(&buffer[..read])?;
+
+            if let Some(e) = output_error.borrow_mut().take() {
+                return Err(e.into());
+            }
+        }
+
+        rewriter.end()?;
+
+        match output_error.into_inner() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes the output of `write` to a temporary file next to `path`, which then replaces `path`.
+///
+/// The file at `path` is left intact if `write` fails.
+fn replace_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().ok_or("not a file")?.to_string_lossy();
+    let temp_path = dir.join(format!(".{}.lolhtml-{}", file_name, process::id()));
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)?;
+
+    let result = (|| {
+        let mut output = BufWriter::new(file);
+
+        write(&mut output)?;
+
+        let file = output.into_inner().map_err(|e| e.into_error())?;
+
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Collects the files in `dir` and its subdirectories, matching the filters.
+///
+/// Symbolic links to directories aren't followed.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    include: &GlobSet,
+    exclude: &GlobSet,
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, include, exclude, files)?;
+        } else if path.is_file() {
+            // NOTE: it's ok to unwrap here as the path is inside the root.
+            let relative = path.strip_prefix(root).unwrap();
+
+            if include.is_match(relative) && !exclude.is_match(relative) {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn glob_set<'g>(globs: impl IntoIterator<Item = &'g str>) -> Result<GlobSet, Box<dyn Error>> {
+    let mut builder = GlobSetBuilder::new();
+
+    for glob in globs {
+        builder.add(Glob::new(glob).map_err(|e| format!("invalid glob `{}`: {}", glob, e))?);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Returns the files to rewrite, each with its path relative to the output directory.
+fn input_files(args: &Args) -> Result<Vec<(PathBuf, PathBuf)>, Box<dyn Error>> {
+    let include = if args.include.is_empty() {
+        glob_set(DEFAULT_INCLUDE)?
+    } else {
+        glob_set(args.include.iter().map(String::as_str))?
+    };
+    let exclude = glob_set(args.exclude.iter().map(String::as_str))?;
+    let mut files = vec![];
+
+    for input in &args.inputs {
+        if input.is_dir() {
+            if !args.recursive {
+                return Err(format!("{}: is a directory, use --recursive", input.display()).into());
+            }
+
+            let mut dir_files = vec![];
+
+            collect_files(input, input, &include, &exclude, &mut dir_files)
+                .map_err(|e| format!("{}: {}", input.display(), e))?;
+
+            files.extend(dir_files.into_iter().map(|path| {
+                // NOTE: it's ok to unwrap here as the path is inside the input directory.
+                let relative = path.strip_prefix(input).unwrap().to_owned();
+                (path, relative)
+            }));
+        } else {
+            let name = input
+                .file_name()
+                .ok_or_else(|| format!("{}: not a file", input.display()))?;
+
+            files.push((input.clone(), PathBuf::from(name)));
+        }
+    }
+
+    Ok(files)
+}
+
+fn run(args: &Args) -> Result<bool, Box<dyn Error>> {
+    let rewriter = Rewriter::new(args)?;
+    let to_stdout = !args.in_place && args.output_dir.is_none();
+
+    if args.inputs.is_empty() || args.inputs == [Path::new("-")] {
+        if !to_stdout {
+            return Err("the standard input can only be rewritten to the standard output".into());
+        }
+
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+
+        rewriter.rewrite(&mut io::stdin().lock(), &mut output)?;
+        output.flush()?;
+
+        return Ok(true);
+    }
+
+    let files = input_files(args)?;
+
+    if to_stdout {
+        if files.len() != 1 || args.recursive {
+            return Err("use --in-place or --output-dir to rewrite several files".into());
+        }
+
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+
+        rewriter
+            .rewrite(&mut File::open(&files[0].0)?, &mut output)
+            .map_err(|e| format!("{}: {}", files[0].0.display(), e))?;
+        output.flush()?;
+
+        return Ok(true);
+    }
+
+    let files = files
+        .into_iter()
+        .map(|(path, relative)| {
+            let output_path = match &args.output_dir {
+                Some(output_dir) => output_dir.join(relative),
+                None => path.clone(),
+            };
+
+            (path, output_path)
+        })
+        .collect::<Vec<_>>();
+    let mut outputs = HashMap::new();
+
+    // NOTE: files with the same name in different directories would overwrite each other's output.
+    for (path, output_path) in &files {
+        if let Some(other) = outputs.insert(output_path, path) {
+            return Err(format!(
+                "{} and {} would both be written to {}",
+                other.display(),
+                path.display(),
+                output_path.display()
+            )
+            .into());
+        }
+    }
+
+    let mut succeeded = true;
+
+    // NOTE: a failure to rewrite one of the files doesn't stop the rest from being rewritten.
+    for (path, output_path) in &files {
+        let result = File::open(path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|mut input| {
+                if let Some(parent) = output_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                replace_atomically(output_path, |output| rewriter.rewrite(&mut input, output))
+            });
+
+        if let Err(e) = result {
+            eprintln!("lolhtml: {}: {}", path.display(), e);
+            succeeded = false;
+        }
+    }
+
+    Ok(succeeded)
+}
+
+fn main() {
+    let args = Args::parse();
+
+    match run(&args) {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("lolhtml: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use pyo3::create_exception;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::rule_set;
+
+pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("RewritingError", py.get_type::<RewritingError>())?;
+    m.add(
+        "ParsingAmbiguityError",
+        py.get_type::<ParsingAmbiguityError>(),
+    )?;
+    m.add(
+        "MemoryLimitExceededError",
+        py.get_type::<MemoryLimitExceededError>(),
+    )?;
+    m.add("ContentHandlerError", py.get_type::<ContentHandlerError>())?;
+    m.add("SelectorError", py.get_type::<SelectorError>())?;
+    m.add("AttributeNameError", py.get_type::<AttributeNameError>())?;
+    m.add("CommentTextError", py.get_type::<CommentTextError>())?;
+    m.add("EncodingError", py.get_type::<EncodingError>())?;
+    m.add("RuleSetError", py.get_type::<RuleSetError>())?;
+    m.add(
+        "RewritableUnitExpiredError",
+        py.get_type::<RewritableUnitExpiredError>(),
+    )?;
+    Ok(())
+}
+
+// NOTE: the base class derives from `RuntimeError` as the bindings used to raise it for every
+// rewriting failure.
+create_exception!(lolhtml, RewritingError, PyRuntimeError);
+create_exception!(lolhtml, ParsingAmbiguityError, RewritingError);
+create_exception!(lolhtml, MemoryLimitExceededError, RewritingError);
+create_exception!(lolhtml, ContentHandlerError, RewritingError);
+create_exception!(lolhtml, SelectorError, RewritingError);
+create_exception!(lolhtml, AttributeNameError, RewritingError);
+create_exception!(lolhtml, CommentTextError, RewritingError);
+create_exception!(lolhtml, EncodingError, RewritingError);
+create_exception!(lolhtml, RewritableUnitExpiredError, PyRuntimeError);
+create_exception!(lolhtml, RuleSetError, PyValueError);
+
+/// Converts a lol_html rewriting error into a Python exception.
+///
+/// Exceptions raised by Python content handlers are propagated as is, so that they can be told
+/// apart from the errors caused by the input.
+pub(crate) fn rewriting_error_to_pyerr(
+    py: Python<'_>,
+    e: lol_html::errors::RewritingError,
+) -> PyErr {
+    match e {
+        lol_html::errors::RewritingError::ContentHandlerError(mut inner) => {
+            if let Some(pyerr) = inner.downcast_mut::<PyErr>() {
+                pyerr.clone_ref(py)
+            } else {
+                ContentHandlerError::new_err(inner.to_string())
+            }
+        }
+        lol_html::errors::RewritingError::MemoryLimitExceeded(inner) => {
+            MemoryLimitExceededError::new_err(inner.to_string())
+        }
+        lol_html::errors::RewritingError::ParsingAmbiguity(inner) => {
+            ParsingAmbiguityError::new_err(inner.to_string())
+        }
+    }
+}
+
+/// Converts a rule set error into a Python exception, exposing the index of the rule and the field
+/// at fault as the `index` and `field` attributes of the exception.
+pub(crate) fn rule_set_error_to_pyerr(
+    py: Python<'_>,
+    e: rule_set::RuleSetError,
+) -> PyResult<PyErr> {
+    let err = RuleSetError::new_err(e.to_string());
+
+    err.value(py).setattr("index", e.index)?;
+    err.value(py).setattr("field", e.field)?;
+
+    Ok(err)
+}
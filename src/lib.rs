@@ -1,69 +1,112 @@
+mod ambiguity;
+mod errors;
 mod rewritable_units;
+mod rewriter;
+pub mod rule_set;
+mod rules;
 mod settings;
 
-use std::rc::Rc;
-
-use pyo3::create_exception;
-use pyo3::exceptions::{PyException, PyRuntimeError};
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 
-use self::settings::{PyDocumentContentHandler, PyElementContentHandler};
+use self::rewriter::rewrite;
+use self::settings::{ElementHandler, PyDocumentContentHandler, RewriterSettings};
 
-create_exception!(module, PyRewritingError, PyException);
+// NOTE: rule sets and settings validation are also used by the `lolhtml` binary.
+pub use self::rules::NativeRule;
+pub use self::settings::{memory_settings, parse_encoding, SettingsError};
 
 /// Rewrites given html string with the provided settings.
+///
+/// In the `strict` mode the rewriting fails with `ParsingAmbiguityError` on markup lol_html can't
+/// parse unambiguously. Otherwise it carries on and, if given, calls `on_parsing_ambiguity` with
+/// the error for every such place.
+#[allow(clippy::too_many_arguments)]
 #[pyfunction(
     html,
     "*",
     element_content_handlers = "Vec::new()",
-    document_content_handlers = "Vec::new()"
+    document_content_handlers = "Vec::new()",
+    max_allowed_memory_usage = "None",
+    preallocated_parsing_buffer_size = "None",
+    strict = "true",
+    on_parsing_ambiguity = "None"
 )]
 fn rewrite_str(
     py: Python<'_>,
     html: &str,
-    element_content_handlers: Vec<PyRefMut<'_, PyElementContentHandler>>,
-    document_content_handlers: Vec<PyRefMut<'_, PyDocumentContentHandler>>,
+    element_content_handlers: Vec<ElementHandler>,
+    document_content_handlers: Vec<Py<PyDocumentContentHandler>>,
+    max_allowed_memory_usage: Option<usize>,
+    preallocated_parsing_buffer_size: Option<usize>,
+    strict: bool,
+    on_parsing_ambiguity: Option<PyObject>,
 ) -> PyResult<String> {
-    let element_content_handlers = element_content_handlers
-        .into_iter()
-        .map(|handler| handler.as_element_content_handlers())
-        .collect();
-    let document_content_handlers = document_content_handlers
-        .into_iter()
-        .map(|handler| handler.as_document_content_handlers())
-        .collect();
-    lol_html::rewrite_str(
-        html,
-        lol_html::RewriteStrSettings {
-            element_content_handlers,
-            document_content_handlers,
-            ..Default::default()
-        },
-    )
-    .map_err(|e| {
-        if let lol_html::errors::RewritingError::ContentHandlerError(mut inner) = e {
-            if let Some(pyerr) = inner.downcast_mut::<PyErr>() {
-                return pyerr.clone_ref(py);
-            } else {
-                PyRuntimeError::new_err(inner.to_string())
-            }
-        } else {
-            PyRuntimeError::new_err(e.to_string())
-        }
-    })
+    let settings = RewriterSettings::new(
+        element_content_handlers,
+        document_content_handlers,
+        "utf-8",
+        max_allowed_memory_usage,
+        preallocated_parsing_buffer_size,
+        strict,
+        on_parsing_ambiguity,
+    )?;
+    let output = rewrite(py, html.as_bytes(), &settings)?;
+
+    // NOTE: it's ok to unwrap here as the output is UTF-8 encoded, same as the input.
+    Ok(String::from_utf8(output).unwrap())
 }
 
-#[pyclass(unsendable)]
-#[derive(Clone)]
-struct RewriteStrSettings(Rc<lol_html::RewriteStrSettings<'static, 'static>>);
+/// Rewrites given html bytes with the provided settings.
+///
+/// `encoding` is the label of the document's character encoding, which has to be
+/// ASCII-compatible. The output is produced in the same encoding.
+#[allow(clippy::too_many_arguments)]
+#[pyfunction(
+    html,
+    "*",
+    encoding = "\"utf-8\"",
+    element_content_handlers = "Vec::new()",
+    document_content_handlers = "Vec::new()",
+    max_allowed_memory_usage = "None",
+    preallocated_parsing_buffer_size = "None",
+    strict = "true",
+    on_parsing_ambiguity = "None"
+)]
+fn rewrite_bytes(
+    py: Python<'_>,
+    html: &[u8],
+    encoding: &str,
+    element_content_handlers: Vec<ElementHandler>,
+    document_content_handlers: Vec<Py<PyDocumentContentHandler>>,
+    max_allowed_memory_usage: Option<usize>,
+    preallocated_parsing_buffer_size: Option<usize>,
+    strict: bool,
+    on_parsing_ambiguity: Option<PyObject>,
+) -> PyResult<PyObject> {
+    let settings = RewriterSettings::new(
+        element_content_handlers,
+        document_content_handlers,
+        encoding,
+        max_allowed_memory_usage,
+        preallocated_parsing_buffer_size,
+        strict,
+        on_parsing_ambiguity,
+    )?;
+    let output = rewrite(py, html, &settings)?;
+
+    Ok(PyBytes::new(py, &output).into())
+}
 
 /// Python bindings of lol-html.
 #[pymodule]
 fn lolhtml(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(rewrite_str, m)?)?;
-    m.add_class::<RewriteStrSettings>()?;
-    m.add("RewritingError", py.get_type::<PyRewritingError>())?;
+    m.add_function(wrap_pyfunction!(rewrite_bytes, m)?)?;
+    errors::register(py, m)?;
     rewritable_units::register(py, m)?;
+    rewriter::register(py, m)?;
+    rules::register(py, m)?;
     settings::register(py, m)?;
     Ok(())
 }
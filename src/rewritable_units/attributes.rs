@@ -0,0 +1,226 @@
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+
+use crate::errors::AttributeNameError;
+use crate::rewritable_units::{compare_mapping, element::PyElement, view};
+
+pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyAttributes>()?;
+
+    py.import("collections.abc")?
+        .getattr("MutableMapping")?
+        .call_method1("register", (py.get_type::<PyAttributes>(),))?;
+
+    Ok(())
+}
+
+/// A live `MutableMapping` view of the element's attributes.
+///
+/// All the operations go straight to the element, so the view, as well as the views returned by
+/// `keys()`, `values()` and `items()`, reflects changes made through any other means and expires
+/// together with the element.
+#[pyclass(unsendable, name = "Attributes")]
+pub(crate) struct PyAttributes {
+    element: Py<PyElement>,
+}
+
+impl PyAttributes {
+    pub(crate) fn new(element: Py<PyElement>) -> Self {
+        Self { element }
+    }
+
+    fn get_attribute(&self, py: Python<'_>, name: &str) -> PyResult<Option<String>> {
+        Ok(self.element.borrow(py).get()?.get_attribute(name))
+    }
+
+    fn set_attribute(&self, py: Python<'_>, name: &str, value: &str) -> PyResult<()> {
+        self.element
+            .borrow_mut(py)
+            .get_mut()?
+            .set_attribute(name, value)
+            .map_err(|e| AttributeNameError::new_err(e.to_string()))
+    }
+
+    /// Returns the attribute names in the source order.
+    fn names(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        Ok(self
+            .element
+            .borrow(py)
+            .get()?
+            .attributes()
+            .iter()
+            .map(|attr| attr.name())
+            .collect())
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+
+        for attr in self.element.borrow(py).get()?.attributes() {
+            dict.set_item(attr.name(), attr.value())?;
+        }
+
+        Ok(dict)
+    }
+
+    fn remove_attribute(&self, py: Python<'_>, name: &str) -> PyResult<Option<String>> {
+        let mut element = self.element.borrow_mut(py);
+        let element = element.get_mut()?;
+        let value = element.get_attribute(name);
+
+        element.remove_attribute(name);
+
+        Ok(value)
+    }
+}
+
+fn key_error(name: &str) -> PyErr {
+    PyKeyError::new_err(name.to_owned())
+}
+
+#[pymethods]
+impl PyAttributes {
+    fn __len__(&self, py: Python<'_>) -> PyResult<usize> {
+        Ok(self.element.borrow(py).get()?.attributes().len())
+    }
+
+    fn __contains__(&self, py: Python<'_>, name: &str) -> PyResult<bool> {
+        Ok(self.element.borrow(py).get()?.has_attribute(name))
+    }
+
+    fn __getitem__(&self, py: Python<'_>, name: &str) -> PyResult<String> {
+        self.get_attribute(py, name)?.ok_or_else(|| key_error(name))
+    }
+
+    fn __setitem__(&self, py: Python<'_>, name: &str, value: &str) -> PyResult<()> {
+        self.set_attribute(py, name, value)
+    }
+
+    fn __delitem__(&self, py: Python<'_>, name: &str) -> PyResult<()> {
+        self.remove_attribute(py, name)?
+            .map(drop)
+            .ok_or_else(|| key_error(name))
+    }
+
+    /// Iterates over the attribute names in the source order.
+    ///
+    /// The names are collected upfront, so the attributes can be changed while iterating.
+    fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let names = self.names(py)?;
+
+        names.into_py(py).call_method0(py, "__iter__")
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("Attributes({})", self.to_dict(py)?.repr()?))
+    }
+
+    /// Returns the value of the attribute with `name`, or `default` if there is none.
+    #[args(default = "None")]
+    fn get(&self, py: Python<'_>, name: &str, default: Option<PyObject>) -> PyResult<PyObject> {
+        Ok(match self.get_attribute(py, name)? {
+            Some(value) => value.into_py(py),
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    /// Returns a live view of the attribute names in the source order.
+    fn keys(slf: &PyCell<Self>) -> PyResult<PyObject> {
+        view(slf, "KeysView")
+    }
+
+    /// Returns a live view of the attribute values in the source order.
+    fn values(slf: &PyCell<Self>) -> PyResult<PyObject> {
+        view(slf, "ValuesView")
+    }
+
+    /// Returns a live view of the `(name, value)` pairs of the attributes in the source order.
+    fn items(slf: &PyCell<Self>) -> PyResult<PyObject> {
+        view(slf, "ItemsView")
+    }
+
+    /// Compares the attributes with another mapping, same as `dict` does.
+    fn __richcmp__(&self, py: Python<'_>, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        compare_mapping(self.to_dict(py)?, other, op)
+    }
+
+    /// Sets the attributes from a mapping or an iterable of `(name, value)` pairs, followed by
+    /// the keyword arguments.
+    #[args(other = "None", kwargs = "**")]
+    fn update(
+        &self,
+        py: Python<'_>,
+        other: Option<&PyAny>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<()> {
+        if let Some(other) = other {
+            if other.hasattr("keys")? {
+                for name in other.call_method0("keys")?.iter()? {
+                    let name = name?;
+                    self.set_attribute(py, name.extract()?, other.get_item(name)?.extract()?)?;
+                }
+            } else {
+                for item in other.iter()? {
+                    let (name, value): (&str, &str) = item?.extract()?;
+                    self.set_attribute(py, name, value)?;
+                }
+            }
+        }
+
+        for (name, value) in kwargs.into_iter().flatten() {
+            self.set_attribute(py, name.extract()?, value.extract()?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the attribute with `name` and returns its value.
+    ///
+    /// Returns `default` if there is no such attribute, raising `KeyError` if it isn't given.
+    #[args(default = "*")]
+    fn pop(&self, py: Python<'_>, name: &str, default: &PyTuple) -> PyResult<PyObject> {
+        match self.remove_attribute(py, name)? {
+            Some(value) => Ok(value.into_py(py)),
+            None if !default.is_empty() => Ok(default.get_item(0)?.into_py(py)),
+            None => Err(key_error(name)),
+        }
+    }
+
+    /// Removes the last attribute and returns its `(name, value)` pair.
+    ///
+    /// Raises `KeyError` if there are no attributes.
+    fn popitem(&self, py: Python<'_>) -> PyResult<(String, String)> {
+        let name = self
+            .names(py)?
+            .pop()
+            .ok_or_else(|| PyKeyError::new_err("popitem(): the element has no attributes"))?;
+        // NOTE: it's ok to unwrap here as the attribute has just been found.
+        let value = self.remove_attribute(py, &name)?.unwrap();
+
+        Ok((name, value))
+    }
+
+    /// Returns the value of the attribute with `name`, setting it to `default` first if there is
+    /// no such attribute.
+    #[args(default = "\"\"")]
+    fn setdefault(&self, py: Python<'_>, name: &str, default: &str) -> PyResult<String> {
+        match self.get_attribute(py, name)? {
+            Some(value) => Ok(value),
+            None => {
+                self.set_attribute(py, name, default)?;
+                Ok(default.to_owned())
+            }
+        }
+    }
+
+    /// Removes all the attributes.
+    fn clear(&self, py: Python<'_>) -> PyResult<()> {
+        for name in self.names(py)? {
+            self.remove_attribute(py, &name)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+use crate::rewritable_units::element::PyElement;
+
+pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyClassList>()?;
+    Ok(())
+}
+
+/// [ASCII whitespace] that separates class names.
+///
+/// [ASCII whitespace]: https://infra.spec.whatwg.org/#ascii-whitespace
+const ASCII_WHITESPACE: [char; 5] = ['\t', '\n', '\x0C', '\r', ' '];
+
+/// A live view of the element's classes, mirroring the DOM's [`DOMTokenList`].
+///
+/// Class names are split on ASCII whitespace with duplicates dropped. Any change rewrites the
+/// `class` attribute with the remaining names separated by single spaces, same as browsers do.
+///
+/// [`DOMTokenList`]: https://dom.spec.whatwg.org/#interface-domtokenlist
+#[pyclass(unsendable, name = "ClassList")]
+pub(crate) struct PyClassList {
+    element: Py<PyElement>,
+}
+
+impl PyClassList {
+    pub(crate) fn new(element: Py<PyElement>) -> Self {
+        Self { element }
+    }
+
+    fn classes(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        let value = self.element.borrow(py).get()?.get_attribute("class");
+        let mut classes: Vec<String> = vec![];
+
+        for class in value.iter().flat_map(|v| v.split(ASCII_WHITESPACE)) {
+            if !class.is_empty() && !classes.iter().any(|c| c == class) {
+                classes.push(class.to_owned());
+            }
+        }
+
+        Ok(classes)
+    }
+
+    /// Runs the DOM's [update steps], writing `classes` back to the `class` attribute.
+    ///
+    /// [update steps]: https://dom.spec.whatwg.org/#concept-dtl-update
+    fn update(&self, py: Python<'_>, classes: &[String]) -> PyResult<()> {
+        let mut element = self.element.borrow_mut(py);
+        let element = element.get_mut()?;
+
+        if classes.is_empty() && !element.has_attribute("class") {
+            return Ok(());
+        }
+
+        // NOTE: it's ok to unwrap here as `class` is a valid attribute name.
+        element.set_attribute("class", &classes.join(" ")).unwrap();
+
+        Ok(())
+    }
+}
+
+/// Validates a class name the way `DOMTokenList` methods do.
+fn validate(class: &str) -> PyResult<&str> {
+    if class.is_empty() {
+        Err(PyValueError::new_err("Class name can't be empty."))
+    } else if class.contains(ASCII_WHITESPACE) {
+        Err(PyValueError::new_err(format!(
+            "Class name `{}` can't contain whitespace.",
+            class
+        )))
+    } else {
+        Ok(class)
+    }
+}
+
+#[pymethods]
+impl PyClassList {
+    fn __len__(&self, py: Python<'_>) -> PyResult<usize> {
+        Ok(self.classes(py)?.len())
+    }
+
+    fn __contains__(&self, py: Python<'_>, class: &str) -> PyResult<bool> {
+        self.contains(py, class)
+    }
+
+    /// Iterates over the class names in the source order.
+    fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.classes(py)?.into_py(py).call_method0(py, "__iter__")
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("ClassList({:?})", self.classes(py)?.join(" ")))
+    }
+
+    /// Returns `True` if the element has the `class`.
+    fn contains(&self, py: Python<'_>, class: &str) -> PyResult<bool> {
+        Ok(self.classes(py)?.iter().any(|c| c == class))
+    }
+
+    /// Adds the given classes, skipping the ones the element already has.
+    #[args(classes = "*")]
+    fn add(&self, py: Python<'_>, classes: &PyTuple) -> PyResult<()> {
+        let mut current = self.classes(py)?;
+
+        for class in classes {
+            let class = validate(class.extract()?)?;
+
+            if !current.iter().any(|c| c == class) {
+                current.push(class.to_owned());
+            }
+        }
+
+        self.update(py, &current)
+    }
+
+    /// Removes the given classes, if present.
+    #[args(classes = "*")]
+    fn remove(&self, py: Python<'_>, classes: &PyTuple) -> PyResult<()> {
+        let mut current = self.classes(py)?;
+
+        for class in classes {
+            let class = validate(class.extract()?)?;
+
+            current.retain(|c| c != class);
+        }
+
+        self.update(py, &current)
+    }
+
+    /// Removes the `class` if present and adds it otherwise.
+    ///
+    /// If `force` is given, the class is only added if it is `True` and only removed if it is
+    /// `False`. Returns `True` if the element has the class afterwards.
+    #[args(force = "None")]
+    fn toggle(&self, py: Python<'_>, class: &str, force: Option<bool>) -> PyResult<bool> {
+        let class = validate(class)?;
+        let mut current = self.classes(py)?;
+        let present = current.iter().any(|c| c == class);
+
+        match (present, force) {
+            (true, Some(true)) | (false, Some(false)) => return Ok(present),
+            (true, _) => current.retain(|c| c != class),
+            (false, _) => current.push(class.to_owned()),
+        }
+
+        self.update(py, &current)?;
+
+        Ok(!present)
+    }
+
+    /// Replaces the `old` class with the `new` one.
+    ///
+    /// Returns `False` without changing anything if the element doesn't have the `old` class.
+    fn replace(&self, py: Python<'_>, old: &str, new: &str) -> PyResult<bool> {
+        let (old, new) = (validate(old)?, validate(new)?);
+        let current = self.classes(py)?;
+
+        if !current.iter().any(|c| c == old) {
+            return Ok(false);
+        }
+
+        let mut replaced: Vec<String> = vec![];
+
+        for class in current {
+            let class = if class == old { new.to_owned() } else { class };
+
+            if !replaced.contains(&class) {
+                replaced.push(class);
+            }
+        }
+
+        self.update(py, &replaced)?;
+
+        Ok(true)
+    }
+}
@@ -1,7 +1,7 @@
 use lol_html::html_content::DocumentEnd;
 use pyo3::prelude::*;
 
-use crate::rewritable_units::PyContentType;
+use crate::rewritable_units::{Expirable, Expire, PyContentType, Replace};
 
 pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyDocumentEnd>()?;
@@ -9,17 +9,26 @@ pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
 }
 
 #[pyclass(unsendable)]
-pub(crate) struct PyDocumentEnd(&'static mut DocumentEnd<'static>);
+pub(crate) struct PyDocumentEnd(Expirable<DocumentEnd<'static>>);
 
 impl PyDocumentEnd {
     pub fn new(end: &'static mut DocumentEnd<'static>) -> Self {
-        Self(end)
+        Self(Expirable::new(end))
     }
 }
 
+impl Expire for PyDocumentEnd {
+    fn expire(&mut self) {
+        self.0.expire()
+    }
+}
+
+impl Replace for PyDocumentEnd {}
+
 #[pymethods]
 impl PyDocumentEnd {
-    pub fn append(&mut self, content: &str, content_type: PyContentType) {
-        self.0.append(content, content_type.into())
+    pub fn append(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.append(content, content_type.into());
+        Ok(())
     }
 }
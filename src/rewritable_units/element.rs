@@ -1,18 +1,41 @@
+use std::error::Error;
+
 use lol_html::html_content::{Element, EndTag};
-use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
 
-use crate::rewritable_units::{tokens::end_tag::PyEndTag, PyContentType};
+use crate::ambiguity::EndTagTracker;
+use crate::errors::{AttributeNameError, RewritingError};
+use crate::rewritable_units::{
+    attributes::PyAttributes, call_with_unit, class_list::PyClassList, style::PyStyle,
+    tokens::end_tag::PyEndTag, Expirable, Expire, PyContentType, Replace,
+};
 
 pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyElement>()?;
-    m.add("TagNameError", py.get_type::<PyTagNameError>())?;
-    m.add("EndTagError", py.get_type::<PyEndTagError>())?;
+    m.add("TagNameError", py.get_type::<TagNameError>())?;
+    m.add("EndTagError", py.get_type::<EndTagError>())?;
     Ok(())
 }
 
-pyo3::create_exception!(module, PyTagNameError, PyException);
-pyo3::create_exception!(module, PyEndTagError, PyException);
+pyo3::create_exception!(lolhtml, TagNameError, RewritingError);
+pyo3::create_exception!(lolhtml, EndTagError, RewritingError);
+
+/// Sets the end tag handler of the `element`, replacing the ones set before.
+///
+/// Returns `false` if the element has no end tag, e.g. if it's a void element.
+pub(crate) fn set_end_tag_handler<H>(element: &mut Element, handler: H) -> bool
+where
+    H: FnOnce(&mut EndTag) -> Result<(), Box<dyn Error + Send + Sync>> + 'static,
+{
+    match element.end_tag_handlers() {
+        Some(handlers) => {
+            handlers.clear();
+            handlers.push(Box::new(handler));
+            true
+        }
+        None => false,
+    }
+}
 
 #[pyclass]
 pub(crate) struct Attribute {
@@ -23,11 +46,41 @@ pub(crate) struct Attribute {
 }
 
 #[pyclass(unsendable, name = "Element")]
-pub(crate) struct PyElement(&'static mut Element<'static, 'static>);
+pub(crate) struct PyElement(
+    Expirable<Element<'static, 'static>>,
+    /// The ambiguity tracker of the rewrite, which has to see the end tag of the element.
+    Option<EndTagTracker>,
+);
 
 impl PyElement {
-    pub fn new(element: &'static mut Element) -> Self {
-        Self(element)
+    pub fn new(element: &'static mut Element, tracker: Option<EndTagTracker>) -> Self {
+        Self(Expirable::new(element), tracker)
+    }
+
+    #[inline]
+    pub(crate) fn get(&self) -> PyResult<&Element<'static, 'static>> {
+        self.0.get()
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self) -> PyResult<&mut Element<'static, 'static>> {
+        self.0.get_mut()
+    }
+}
+
+impl Expire for PyElement {
+    fn expire(&mut self) {
+        self.0.expire()
+    }
+}
+
+impl Replace for PyElement {
+    fn remove(&mut self) -> PyResult<()> {
+        PyElement::remove(self)
+    }
+
+    fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        PyElement::replace(self, content, content_type)
     }
 }
 
@@ -35,52 +88,74 @@ impl PyElement {
 impl PyElement {
     /// Returns the tag name of the element.
     #[inline]
-    fn tag_name(&self) -> String {
-        self.0.tag_name()
+    fn tag_name(&self) -> PyResult<String> {
+        Ok(self.0.get()?.tag_name())
     }
 
     /// Sets the tag name of the element.
     #[inline]
     fn set_tag_name(&mut self, name: &str) -> PyResult<()> {
-        Ok(self
-            .0
+        self.0
+            .get_mut()?
             .set_tag_name(name)
-            .map_err(|e| PyTagNameError::new_err(e.to_string()))?)
+            .map_err(|e| TagNameError::new_err(e.to_string()))
     }
 
     /// Returns the [namespace URI] of the element.
     ///
     /// [namespace URI]: https://developer.mozilla.org/en-US/docs/Web/API/Element/namespaceURI
     #[inline]
-    fn namespace_uri(&self) -> &'static str {
-        self.0.namespace_uri()
+    fn namespace_uri(&self) -> PyResult<&'static str> {
+        Ok(self.0.get()?.namespace_uri())
     }
 
     /// Returns an immutable collection of element's attributes.
     #[inline]
-    fn attributes(&self) -> Vec<Attribute> {
-        self.0
+    fn attributes(&self) -> PyResult<Vec<Attribute>> {
+        Ok(self
+            .0
+            .get()?
             .attributes()
             .iter()
             .map(|attr| Attribute {
                 name: attr.name(),
                 value: attr.value(),
             })
-            .collect()
+            .collect())
+    }
+
+    /// A live mapping of the element's attribute names to their values.
+    ///
+    /// Iteration follows the order of the attributes in the source.
+    #[getter]
+    fn attrs(slf: PyRef<'_, Self>) -> PyAttributes {
+        PyAttributes::new(slf.into())
+    }
+
+    /// A live list of the element's classes.
+    #[getter]
+    fn class_list(slf: PyRef<'_, Self>) -> PyClassList {
+        PyClassList::new(slf.into())
+    }
+
+    /// A live mapping of the CSS declarations in the element's `style` attribute.
+    #[getter]
+    fn style(slf: PyRef<'_, Self>) -> PyStyle {
+        PyStyle::new(slf.into())
     }
 
     /// Returns the value of an attribute with the `name`.
     ///
     /// Returns `None` if the element doesn't have an attribute with the `name`.
     #[inline]
-    fn get_attribute(&self, name: &str) -> Option<String> {
-        self.0.get_attribute(name)
+    fn get_attribute(&self, name: &str) -> PyResult<Option<String>> {
+        Ok(self.0.get()?.get_attribute(name))
     }
 
     /// Returns `true` if the element has an attribute with `name`.
     #[inline]
-    fn has_attribute(&self, name: &str) -> bool {
-        self.0.has_attribute(name)
+    fn has_attribute(&self, name: &str) -> PyResult<bool> {
+        Ok(self.0.get()?.has_attribute(name))
     }
 
     /// Sets `value` of element's attribute with `name`.
@@ -89,32 +164,35 @@ impl PyElement {
     /// to the element with `name` and `value`.
     #[inline]
     fn set_attribute(&mut self, name: &str, value: &str) -> PyResult<()> {
-        Ok(self
-            .0
+        self.0
+            .get_mut()?
             .set_attribute(name, value)
-            .map_err(|_e| pyo3::exceptions::PyRuntimeError::new_err("something went wrong"))?)
+            .map_err(|e| AttributeNameError::new_err(e.to_string()))
     }
 
     /// Removes an attribute with the `name` if it is present.
     #[inline]
-    fn remove_attribute(&mut self, name: &str) {
-        self.0.remove_attribute(name)
+    fn remove_attribute(&mut self, name: &str) -> PyResult<()> {
+        self.0.get_mut()?.remove_attribute(name);
+        Ok(())
     }
 
     /// Inserts `content` before the element.
     ///
     /// Consequent calls to the method append `content` to the previously inserted content.
     #[inline]
-    fn before(&mut self, content: &str, content_type: PyContentType) {
-        self.0.before(content, content_type.into())
+    fn before(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.before(content, content_type.into());
+        Ok(())
     }
 
     /// Inserts `content` after the element.
     ///
     /// Consequent calls to the method prepend `content` to the previously inserted content.
     #[inline]
-    fn after(&mut self, content: &str, content_type: PyContentType) {
-        self.0.after(content, content_type.into())
+    fn after(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.after(content, content_type.into());
+        Ok(())
     }
 
     /// Prepends `content` to the element's inner content, i.e. inserts content right after
@@ -125,8 +203,9 @@ impl PyElement {
     ///
     /// [empty element]: https://developer.mozilla.org/en-US/docs/Glossary/Empty_element
     #[inline]
-    fn prepend(&mut self, content: &str, content_type: PyContentType) {
-        self.0.prepend(content, content_type.into())
+    fn prepend(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.prepend(content, content_type.into());
+        Ok(())
     }
 
     /// Appends `content` to the element's inner content, i.e. inserts content right before
@@ -137,8 +216,9 @@ impl PyElement {
     ///
     /// [empty element]: https://developer.mozilla.org/en-US/docs/Glossary/Empty_element
     #[inline]
-    fn append(&mut self, content: &str, content_type: PyContentType) {
-        self.0.append(content, content_type.into())
+    fn append(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.append(content, content_type.into());
+        Ok(())
     }
 
     /// Replaces inner content of the element with `content`.
@@ -148,34 +228,40 @@ impl PyElement {
     ///
     /// [empty element]: https://developer.mozilla.org/en-US/docs/Glossary/Empty_element
     #[inline]
-    fn set_inner_content(&mut self, content: &str, content_type: PyContentType) {
-        self.0.set_inner_content(content, content_type.into())
+    fn set_inner_content(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0
+            .get_mut()?
+            .set_inner_content(content, content_type.into());
+        Ok(())
     }
 
     /// Replaces the element and its inner content with `content`.
     ///
     /// Consequent calls to the method overwrite previously inserted content.
     #[inline]
-    fn replace(&mut self, content: &str, content_type: PyContentType) {
-        self.0.replace(content, content_type.into())
+    fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.replace(content, content_type.into());
+        Ok(())
     }
 
     /// Removes the element and its inner content.
     #[inline]
-    fn remove(&mut self) {
-        self.0.remove()
+    fn remove(&mut self) -> PyResult<()> {
+        self.0.get_mut()?.remove();
+        Ok(())
     }
 
     /// Removes the element, but keeps its content. I.e. remove start and end tags of the element.
     #[inline]
-    fn remove_and_keep_content(&mut self) {
-        self.0.remove_and_keep_content()
+    fn remove_and_keep_content(&mut self) -> PyResult<()> {
+        self.0.get_mut()?.remove_and_keep_content();
+        Ok(())
     }
 
     /// Returns `true` if the element has been removed or replaced with some content.
     #[inline]
-    fn removed(&self) -> bool {
-        self.0.removed()
+    fn removed(&self) -> PyResult<bool> {
+        Ok(self.0.get()?.removed())
     }
 
     /// Sets a handler to run when the end tag is reached.
@@ -183,16 +269,18 @@ impl PyElement {
     /// Subsequent calls to the method on the same element replace the previous handler.
     fn on_end_tag(&mut self, handler: Option<PyObject>) -> PyResult<()> {
         if let Some(callback) = handler {
-            let handler = move |end: &mut EndTag| {
+            let handler = EndTagTracker::wrap(self.1.clone(), move |end: &mut EndTag| {
                 let end: &'static mut EndTag<'static> = unsafe { std::mem::transmute(end) };
                 Python::with_gil(|py| {
-                    let _result = callback.call(py, (PyEndTag::new(end),), None)?;
+                    let _result = call_with_unit(py, &callback, PyEndTag::new(end))?;
                     Ok(())
                 })
-            };
-            self.0
-                .on_end_tag(handler)
-                .map_err(|e| PyEndTagError::new_err(e.to_string()))
+            });
+            if set_end_tag_handler(self.0.get_mut()?, handler) {
+                Ok(())
+            } else {
+                Err(EndTagError::new_err("No end tag."))
+            }
         } else {
             Ok(())
         }
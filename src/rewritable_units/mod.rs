@@ -1,12 +1,24 @@
+pub(crate) mod attributes;
+pub(crate) mod class_list;
 pub(crate) mod document_end;
 pub(crate) mod element;
+pub(crate) mod style;
 pub(crate) mod tokens;
 
 use lol_html::html_content::ContentType;
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyType};
+use pyo3::{PyClass, PyTypeInfo};
+
+use crate::errors::RewritableUnitExpiredError;
 
 pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     element::register(py, m)?;
+    attributes::register(py, m)?;
+    class_list::register(py, m)?;
+    style::register(py, m)?;
     document_end::register(py, m)?;
     tokens::register(py, m)?;
     m.add_class::<PyContentType>()?;
@@ -28,3 +40,160 @@ impl From<PyContentType> for ContentType {
         }
     }
 }
+
+/// A reference to a rewritable unit that is only valid while the content handler it was passed
+/// to is running.
+///
+/// lol_html only lends rewritable units to content handlers, while Python code is free to keep
+/// the wrapping objects around. Once expired, any access to the unit raises
+/// `RewritableUnitExpiredError` instead of touching freed memory.
+pub(crate) struct Expirable<T: 'static>(Option<&'static mut T>);
+
+impl<T> Expirable<T> {
+    pub fn new(unit: &'static mut T) -> Self {
+        Self(Some(unit))
+    }
+
+    #[inline]
+    pub fn get(&self) -> PyResult<&T> {
+        self.0.as_deref().ok_or_else(expired)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> PyResult<&mut T> {
+        self.0.as_deref_mut().ok_or_else(expired)
+    }
+
+    #[inline]
+    pub fn expire(&mut self) {
+        self.0 = None;
+    }
+}
+
+/// Wraps a mapping into a view class of `collections.abc`, which reads it through the mapping
+/// methods and thus stays live.
+fn view<T: PyClass>(slf: &PyCell<T>, class: &str) -> PyResult<PyObject> {
+    Ok(slf
+        .py()
+        .import("collections.abc")?
+        .getattr(class)?
+        .call1((slf,))?
+        .into())
+}
+
+/// Compares a mapping, copied into `dict`, with `other` the way `dict` does, i.e. only for
+/// (in)equality and only with other mappings.
+fn compare_mapping(dict: &PyDict, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+    let py = dict.py();
+    let mapping = py
+        .import("collections.abc")?
+        .getattr("Mapping")?
+        .downcast::<PyType>()?;
+
+    if !matches!(op, CompareOp::Eq | CompareOp::Ne) || !other.is_instance(mapping)? {
+        return Ok(py.NotImplemented());
+    }
+
+    let other = PyDict::from_sequence(py, other.call_method0("items")?.into())?;
+
+    Ok(dict.rich_compare(other, op)?.into())
+}
+
+fn expired() -> PyErr {
+    RewritableUnitExpiredError::new_err(
+        "The rewritable unit can only be used inside the content handler it was passed to.",
+    )
+}
+
+/// Python wrappers of rewritable units that can be invalidated.
+pub(crate) trait Expire {
+    fn expire(&mut self);
+}
+
+/// Python wrappers of rewritable units that can be removed or replaced by the value returned
+/// from a content handler.
+///
+/// Units that don't support these mutations keep the default implementations, which raise
+/// `TypeError`.
+pub(crate) trait Replace: PyTypeInfo {
+    fn remove(&mut self) -> PyResult<()> {
+        Err(PyTypeError::new_err(format!(
+            "{} can't be removed by returning `False` from the content handler.",
+            Self::NAME
+        )))
+    }
+
+    fn replace(&mut self, _content: &str, _content_type: PyContentType) -> PyResult<()> {
+        Err(PyTypeError::new_err(format!(
+            "{} can't be replaced by returning content from the content handler.",
+            Self::NAME
+        )))
+    }
+}
+
+/// Applies the value returned from a content handler to the `unit`.
+///
+/// `None` leaves the unit as is, `False` removes it, while `str` and objects implementing
+/// `__html__` (e.g. `markupsafe.Markup`) replace it as text and as HTML respectively.
+fn apply_return_value<U: Replace>(unit: &mut U, value: &PyAny) -> PyResult<()> {
+    if value.is_none() {
+        Ok(())
+    } else if value.is(PyBool::new(value.py(), false)) {
+        unit.remove()
+    } else if value.hasattr("__html__")? {
+        unit.replace(
+            value.call_method0("__html__")?.extract()?,
+            PyContentType::Html,
+        )
+    } else if let Ok(text) = value.extract() {
+        unit.replace(text, PyContentType::Text)
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "Content handlers can only return `None`, `False`, `str` or an object implementing \
+             `__html__`, not `{}`.",
+            value.get_type().name()?
+        )))
+    }
+}
+
+/// Calls `handler` with the wrapped rewritable `unit`, applying the returned value to the unit
+/// and expiring the unit once the handler returns.
+pub(crate) fn call_with_unit<U>(py: Python<'_>, handler: &PyObject, unit: U) -> PyResult<PyObject>
+where
+    U: PyClass + Expire + Replace + Into<PyClassInitializer<U>>,
+{
+    with_unit(py, unit, |unit| handler.call1(py, (unit,)))
+}
+
+/// Same as [`call_with_unit`], but also passes `arg` to the handler after the unit.
+pub(crate) fn call_with_unit_and_arg<U, A>(
+    py: Python<'_>,
+    handler: &PyObject,
+    unit: U,
+    arg: A,
+) -> PyResult<PyObject>
+where
+    U: PyClass + Expire + Replace + Into<PyClassInitializer<U>>,
+    A: IntoPy<PyObject>,
+{
+    with_unit(py, unit, |unit| handler.call1(py, (unit, arg)))
+}
+
+fn with_unit<U>(
+    py: Python<'_>,
+    unit: U,
+    call: impl FnOnce(Py<U>) -> PyResult<PyObject>,
+) -> PyResult<PyObject>
+where
+    U: PyClass + Expire + Replace + Into<PyClassInitializer<U>>,
+{
+    let unit = Py::new(py, unit)?;
+    let result = call(unit.clone_ref(py)).and_then(|value| {
+        apply_return_value(&mut *unit.borrow_mut(py), value.as_ref(py))?;
+        Ok(value)
+    });
+
+    unit.borrow_mut(py).expire();
+
+    result
+}
@@ -0,0 +1,559 @@
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+
+use crate::rewritable_units::{compare_mapping, element::PyElement, view};
+
+pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyStyle>()?;
+
+    py.import("collections.abc")?
+        .getattr("MutableMapping")?
+        .call_method1("register", (py.get_type::<PyStyle>(),))?;
+
+    Ok(())
+}
+
+/// A declaration of the `style` attribute.
+struct Declaration {
+    /// Property name, lowercased unless it's a custom property. `None` for the text that can't
+    /// be parsed as a declaration, which is kept as is.
+    name: Option<String>,
+    value: String,
+    important: bool,
+    /// Source text of the declaration, dropped once the declaration is changed.
+    raw: Option<String>,
+    /// Whitespace around the declaration, which is kept even if the declaration is changed.
+    leading: String,
+    trailing: String,
+}
+
+impl Declaration {
+    fn new(name: String, value: &str, important: bool) -> Self {
+        Self {
+            name: Some(name),
+            value: value.to_owned(),
+            important,
+            raw: None,
+            leading: String::new(),
+            trailing: String::new(),
+        }
+    }
+
+    /// Parses the text between two separators, which may be surrounded by whitespace.
+    fn parse(part: &str) -> Self {
+        let source = part.trim();
+        let leading = part[..part.len() - part.trim_start().len()].to_owned();
+        let trailing = part[part.trim_end().len()..].to_owned();
+        let raw = Some(source.to_owned());
+
+        let colon = match split_top_level(source, ':').next() {
+            Some(name) if name.len() < source.len() => name.len(),
+            _ => {
+                return Self {
+                    name: None,
+                    value: String::new(),
+                    important: false,
+                    raw,
+                    leading,
+                    trailing,
+                }
+            }
+        };
+
+        let (value, important) = strip_important(source[colon + 1..].trim());
+
+        Self {
+            name: Some(normalize_name(strip_comments(&source[..colon]).trim())),
+            value: value.to_owned(),
+            important,
+            raw,
+            leading,
+            trailing,
+        }
+    }
+
+    /// Whether it's the whitespace between two separators or after the last one.
+    fn is_blank(&self) -> bool {
+        self.name.is_none() && self.raw.as_deref() == Some("")
+    }
+
+    fn serialize(&self) -> String {
+        let source = match (&self.raw, &self.name) {
+            (Some(raw), _) => raw.clone(),
+            (None, Some(name)) if self.important => format!("{}: {} !important", name, self.value),
+            (None, Some(name)) => format!("{}: {}", name, self.value),
+            (None, None) => String::new(),
+        };
+
+        format!("{}{}{}", self.leading, source, self.trailing)
+    }
+}
+
+/// Removes the declarations not matching `keep`, along with their separators.
+fn retain_declarations(
+    declarations: &mut Vec<Declaration>,
+    keep: impl FnMut(&Declaration) -> bool,
+) {
+    // NOTE: the whitespace before the first declaration is kept, even if it's removed.
+    let leading = declarations
+        .first()
+        .map(|decl| decl.leading.clone())
+        .unwrap_or_default();
+
+    declarations.retain(keep);
+
+    if declarations.iter().all(Declaration::is_blank) {
+        declarations.clear();
+    } else {
+        declarations[0].leading = leading;
+    }
+}
+
+/// Walks `source`, calling `visit` with every character outside comments, its index and whether
+/// it is outside quotes, parentheses and brackets.
+///
+/// Returns `false` if a quote, parenthesis, bracket or comment isn't closed, or if `source` ends
+/// with a backslash.
+fn scan(source: &str, mut visit: impl FnMut(usize, char, bool)) -> bool {
+    let mut balanced = true;
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        match (quote, ch) {
+            // NOTE: an escaped character is never a separator, and a trailing backslash would
+            // escape whatever follows the source.
+            (_, '\\') => {
+                visit(i, ch, false);
+
+                match chars.next() {
+                    Some((i, ch)) => visit(i, ch, false),
+                    None => balanced = false,
+                }
+
+                continue;
+            }
+            (Some(q), ch) if ch == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '/') if matches!(chars.peek(), Some((_, '*'))) => {
+                chars.next();
+                balanced = false;
+
+                while let Some((_, ch)) = chars.next() {
+                    if ch == '*' && matches!(chars.peek(), Some((_, '/'))) {
+                        chars.next();
+                        balanced = true;
+                        break;
+                    }
+                }
+
+                continue;
+            }
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') if depth == 0 => balanced = false,
+            (None, ')' | ']') => depth -= 1,
+            _ => (),
+        }
+
+        visit(i, ch, quote.is_none() && depth == 0);
+    }
+
+    balanced && quote.is_none() && depth == 0
+}
+
+/// Splits `source` on `separator`s that aren't inside quotes, parentheses or comments.
+fn split_top_level(source: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut parts = vec![];
+    let mut start = 0;
+
+    scan(source, |i, ch, top_level| {
+        if top_level && ch == separator {
+            parts.push(&source[start..i]);
+            start = i + ch.len_utf8();
+        }
+    });
+
+    parts.push(&source[start..]);
+    parts.into_iter()
+}
+
+/// Removes the comments from `source`.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+
+    scan(source, |_, ch, _| stripped.push(ch));
+
+    stripped
+}
+
+/// Splits the `!important` flag off the `value`.
+fn strip_important(value: &str) -> (&str, bool) {
+    match value.rfind('!') {
+        Some(bang) if value[bang + 1..].trim().eq_ignore_ascii_case("important") => {
+            (value[..bang].trim_end(), true)
+        }
+        _ => (value, false),
+    }
+}
+
+/// Property names are case-insensitive, except for custom properties.
+fn normalize_name(name: &str) -> String {
+    if name.starts_with("--") {
+        name.to_owned()
+    } else {
+        name.to_ascii_lowercase()
+    }
+}
+
+/// A live `MutableMapping` view of the CSS declarations in the element's `style` attribute.
+///
+/// Only changed declarations are re-serialized, the rest of them, as well as the separators
+/// between them, are written back as they are in the source.
+#[pyclass(unsendable, name = "Style")]
+pub(crate) struct PyStyle {
+    element: Py<PyElement>,
+}
+
+impl PyStyle {
+    pub(crate) fn new(element: Py<PyElement>) -> Self {
+        Self { element }
+    }
+
+    /// Parses the declarations, keeping the text that can't be parsed and the whitespace
+    /// between the separators as declarations without a name.
+    fn declarations(&self, py: Python<'_>) -> PyResult<Vec<Declaration>> {
+        let style = self
+            .element
+            .borrow(py)
+            .get()?
+            .get_attribute("style")
+            .unwrap_or_default();
+
+        if style.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(split_top_level(&style, ';')
+            .map(Declaration::parse)
+            .collect())
+    }
+
+    /// Returns the declared property names in the source order.
+    fn names(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        let mut names: Vec<String> = vec![];
+
+        for name in self.declarations(py)?.into_iter().filter_map(|d| d.name) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+
+        for name in self.names(py)? {
+            dict.set_item(&name, self.__getitem__(py, &name)?)?;
+        }
+
+        Ok(dict)
+    }
+
+    /// Returns the declaration that takes effect for the property with `name`.
+    ///
+    /// Same as in CSS, the last declaration wins, unless an earlier one is `!important`.
+    fn find(&self, py: Python<'_>, name: &str) -> PyResult<Option<(String, bool)>> {
+        let name = normalize_name(name);
+        let mut found: Option<(String, bool)> = None;
+
+        for decl in self.declarations(py)? {
+            if decl.name.as_deref() == Some(name.as_str())
+                && !matches!(found, Some((_, true)) if !decl.important)
+            {
+                found = Some((decl.value, decl.important));
+            }
+        }
+
+        Ok(found)
+    }
+
+    fn write(&self, py: Python<'_>, declarations: &[Declaration]) -> PyResult<()> {
+        let style = declarations
+            .iter()
+            .map(Declaration::serialize)
+            .collect::<Vec<_>>()
+            .join(";");
+
+        // NOTE: it's ok to unwrap here as `style` is a valid attribute name.
+        self.element
+            .borrow_mut(py)
+            .get_mut()?
+            .set_attribute("style", &style)
+            .unwrap();
+
+        Ok(())
+    }
+}
+
+fn key_error(name: &str) -> PyErr {
+    PyKeyError::new_err(name.to_owned())
+}
+
+#[pymethods]
+impl PyStyle {
+    fn __len__(&self, py: Python<'_>) -> PyResult<usize> {
+        Ok(self.names(py)?.len())
+    }
+
+    fn __contains__(&self, py: Python<'_>, name: &str) -> PyResult<bool> {
+        Ok(self.find(py, name)?.is_some())
+    }
+
+    fn __getitem__(&self, py: Python<'_>, name: &str) -> PyResult<String> {
+        self.find(py, name)?
+            .map(|(value, _)| value)
+            .ok_or_else(|| key_error(name))
+    }
+
+    fn __setitem__(&self, py: Python<'_>, name: &str, value: &str) -> PyResult<()> {
+        self.set(py, name, value, false)
+    }
+
+    fn __delitem__(&self, py: Python<'_>, name: &str) -> PyResult<()> {
+        self.remove(py, name)?
+            .map(drop)
+            .ok_or_else(|| key_error(name))
+    }
+
+    /// Iterates over the property names in the source order.
+    ///
+    /// The names are collected upfront, so the declarations can be changed while iterating.
+    fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.names(py)?.into_py(py).call_method0(py, "__iter__")
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let style = self.element.borrow(py).get()?.get_attribute("style");
+
+        Ok(format!("Style({:?})", style.unwrap_or_default()))
+    }
+
+    /// Returns the value of the property with `name`, or `default` if it isn't declared.
+    #[args(default = "None")]
+    fn get(&self, py: Python<'_>, name: &str, default: Option<PyObject>) -> PyResult<PyObject> {
+        Ok(match self.find(py, name)? {
+            Some((value, _)) => value.into_py(py),
+            None => default.unwrap_or_else(|| py.None()),
+        })
+    }
+
+    /// Returns `"important"` if the property with `name` is declared as `!important` and an empty
+    /// string otherwise.
+    fn priority(&self, py: Python<'_>, name: &str) -> PyResult<&'static str> {
+        Ok(match self.find(py, name)? {
+            Some((_, true)) => "important",
+            _ => "",
+        })
+    }
+
+    /// Returns a live view of the declared property names in the source order.
+    fn keys(slf: &PyCell<Self>) -> PyResult<PyObject> {
+        view(slf, "KeysView")
+    }
+
+    /// Returns a live view of the values of the declared properties in the source order.
+    fn values(slf: &PyCell<Self>) -> PyResult<PyObject> {
+        view(slf, "ValuesView")
+    }
+
+    /// Returns a live view of the `(name, value)` pairs of the declared properties in the source
+    /// order.
+    fn items(slf: &PyCell<Self>) -> PyResult<PyObject> {
+        view(slf, "ItemsView")
+    }
+
+    /// Compares the declared properties with another mapping, same as `dict` does.
+    ///
+    /// The priorities of the properties aren't compared.
+    fn __richcmp__(&self, py: Python<'_>, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        compare_mapping(self.to_dict(py)?, other, op)
+    }
+
+    /// Sets the properties from a mapping or an iterable of `(name, value)` pairs, followed by
+    /// the keyword arguments.
+    #[args(other = "None", kwargs = "**")]
+    fn update(
+        &self,
+        py: Python<'_>,
+        other: Option<&PyAny>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<()> {
+        if let Some(other) = other {
+            if other.hasattr("keys")? {
+                for name in other.call_method0("keys")?.iter()? {
+                    let name = name?;
+                    self.set(py, name.extract()?, other.get_item(name)?.extract()?, false)?;
+                }
+            } else {
+                for item in other.iter()? {
+                    let (name, value): (&str, &str) = item?.extract()?;
+                    self.set(py, name, value, false)?;
+                }
+            }
+        }
+
+        for (name, value) in kwargs.into_iter().flatten() {
+            self.set(py, name.extract()?, value.extract()?, false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes all the declarations of the property with `name` and returns its value.
+    ///
+    /// Returns `default` if the property isn't declared, raising `KeyError` if it isn't given.
+    #[args(default = "*")]
+    fn pop(&self, py: Python<'_>, name: &str, default: &PyTuple) -> PyResult<PyObject> {
+        match self.remove(py, name)? {
+            Some(value) => Ok(value.into_py(py)),
+            None if !default.is_empty() => Ok(default.get_item(0)?.into_py(py)),
+            None => Err(key_error(name)),
+        }
+    }
+
+    /// Removes the last declared property and returns its `(name, value)` pair.
+    ///
+    /// Raises `KeyError` if no properties are declared.
+    fn popitem(&self, py: Python<'_>) -> PyResult<(String, String)> {
+        let name = self
+            .names(py)?
+            .pop()
+            .ok_or_else(|| PyKeyError::new_err("popitem(): no properties are declared"))?;
+        // NOTE: it's ok to unwrap here as the property has just been found.
+        let value = self.remove(py, &name)?.unwrap();
+
+        Ok((name, value))
+    }
+
+    /// Returns the value of the property with `name`, setting it to `default` first if it isn't
+    /// declared.
+    #[args(default = "\"\"")]
+    fn setdefault(&self, py: Python<'_>, name: &str, default: &str) -> PyResult<String> {
+        match self.find(py, name)? {
+            Some((value, _)) => Ok(value),
+            None => {
+                self.set(py, name, default, false)?;
+                Ok(default.trim().to_owned())
+            }
+        }
+    }
+
+    /// Removes all the declarations, keeping the text that can't be parsed as a declaration.
+    fn clear(&self, py: Python<'_>) -> PyResult<()> {
+        let mut declarations = self.declarations(py)?;
+
+        retain_declarations(&mut declarations, |decl| decl.name.is_none());
+
+        self.write(py, &declarations)
+    }
+
+    /// Sets the property with `name` to `value`, optionally marking it as `!important`.
+    ///
+    /// The first declaration of the property is updated in place and the rest of them are
+    /// dropped. A new declaration is appended if the property isn't declared yet.
+    #[args(important = "false")]
+    fn set(&self, py: Python<'_>, name: &str, value: &str, important: bool) -> PyResult<()> {
+        let name = normalize_name(name.trim());
+        let value = value.trim();
+
+        if name.is_empty()
+            || !scan(&name, |_, _, _| ())
+            || split_top_level(&name, ':').count() > 1
+            || name.contains(';')
+        {
+            return Err(PyValueError::new_err(format!(
+                "Invalid property name `{}`.",
+                name
+            )));
+        }
+
+        // NOTE: an unclosed quote or parenthesis would swallow the declarations written after
+        // the value.
+        if !scan(value, |_, _, _| ()) {
+            return Err(PyValueError::new_err(format!(
+                "Invalid value `{}` of the `{}` property, it has an unclosed quote, parenthesis, \
+                 bracket or comment, or ends with a backslash.",
+                value, name
+            )));
+        }
+
+        if split_top_level(value, ';').count() > 1 || strip_important(value).1 {
+            return Err(PyValueError::new_err(format!(
+                "Invalid value `{}` of the `{}` property, use `important=True` for `!important`.",
+                value, name
+            )));
+        }
+
+        let mut declarations = self.declarations(py)?;
+        let mut updated = Declaration::new(name.clone(), value, important);
+
+        match declarations
+            .iter()
+            .position(|decl| decl.name.as_deref() == Some(name.as_str()))
+        {
+            Some(first) => {
+                updated.leading = std::mem::take(&mut declarations[first].leading);
+                updated.trailing = std::mem::take(&mut declarations[first].trailing);
+                declarations[first] = updated;
+
+                let mut i = 0;
+
+                retain_declarations(&mut declarations, |decl| {
+                    i += 1;
+                    i <= first + 1 || decl.name.as_deref() != Some(name.as_str())
+                });
+            }
+            None => {
+                // NOTE: a trailing separator stays the last one.
+                let at = match declarations.last() {
+                    Some(last) if last.is_blank() => declarations.len() - 1,
+                    _ => declarations.len(),
+                };
+
+                if at > 0 {
+                    updated.leading = " ".to_owned();
+                }
+
+                declarations.insert(at, updated);
+            }
+        }
+
+        self.write(py, &declarations)
+    }
+
+    /// Removes all the declarations of the property with `name`.
+    ///
+    /// Returns the value the property had or `None` if it wasn't declared.
+    fn remove(&self, py: Python<'_>, name: &str) -> PyResult<Option<String>> {
+        let value = self.find(py, name)?.map(|(value, _)| value);
+
+        if value.is_some() {
+            let name = normalize_name(name);
+            let mut declarations = self.declarations(py)?;
+
+            retain_declarations(&mut declarations, |decl| {
+                decl.name.as_deref() != Some(name.as_str())
+            });
+
+            self.write(py, &declarations)?;
+        }
+
+        Ok(value)
+    }
+}
@@ -1,7 +1,8 @@
 use lol_html::html_content::Comment;
 use pyo3::prelude::*;
 
-use crate::rewritable_units::PyContentType;
+use crate::errors::CommentTextError;
+use crate::rewritable_units::{Expirable, Expire, PyContentType, Replace};
 
 pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyComment>()?;
@@ -9,11 +10,27 @@ pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
 }
 
 #[pyclass(unsendable)]
-pub(crate) struct PyComment(&'static mut Comment<'static>);
+pub(crate) struct PyComment(Expirable<Comment<'static>>);
 
 impl PyComment {
     pub fn new(end: &'static mut Comment<'static>) -> Self {
-        Self(end)
+        Self(Expirable::new(end))
+    }
+}
+
+impl Expire for PyComment {
+    fn expire(&mut self) {
+        self.0.expire()
+    }
+}
+
+impl Replace for PyComment {
+    fn remove(&mut self) -> PyResult<()> {
+        PyComment::remove(self)
+    }
+
+    fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        PyComment::replace(self, content, content_type)
     }
 }
 
@@ -21,48 +38,59 @@ impl PyComment {
 impl PyComment {
     /// Returns the text of the comment.
     #[inline]
-    pub fn text(&self) -> String {
-        self.0.text()
+    pub fn text(&self) -> PyResult<String> {
+        Ok(self.0.get()?.text())
     }
 
     /// Sets the text of the comment.
-    // #[inline]
-    // pub fn set_text(&mut self, text: &str) -> Result<(), CommentTextError> {
-    // }
+    ///
+    /// Raises `CommentTextError` if the text contains the comment closing sequence (`-->`) or a
+    /// character that can't be represented in the document's encoding.
+    #[inline]
+    pub fn set_text(&mut self, text: &str) -> PyResult<()> {
+        self.0
+            .get_mut()?
+            .set_text(text)
+            .map_err(|e| CommentTextError::new_err(e.to_string()))
+    }
 
     /// Inserts `content` before the comment.
     ///
     /// Consequent calls to the method append `content` to the previously inserted content.
     #[inline]
-    pub fn before(&mut self, content: &str, content_type: PyContentType) {
-        self.0.before(content, content_type.into())
+    pub fn before(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.before(content, content_type.into());
+        Ok(())
     }
 
     /// Inserts `content` after the comment.
     ///
     /// Consequent calls to the method prepend `content` to the previously inserted content.
     #[inline]
-    pub fn after(&mut self, content: &str, content_type: PyContentType) {
-        self.0.after(content, content_type.into())
+    pub fn after(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.after(content, content_type.into());
+        Ok(())
     }
 
     /// Replaces the comment with the `content`.
     ///
     /// Consequent calls to the method overwrite previous replacement content.
     #[inline]
-    pub fn replace(&mut self, content: &str, content_type: PyContentType) {
-        self.0.replace(content, content_type.into())
+    pub fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.replace(content, content_type.into());
+        Ok(())
     }
 
     /// Removes the comment.
     #[inline]
-    pub fn remove(&mut self) {
-        self.0.remove()
+    pub fn remove(&mut self) -> PyResult<()> {
+        self.0.get_mut()?.remove();
+        Ok(())
     }
 
     /// Returns `true` if the comment has been replaced or removed.
     #[inline]
-    pub fn removed(&self) -> bool {
-        self.0.removed()
+    pub fn removed(&self) -> PyResult<bool> {
+        Ok(self.0.get()?.removed())
     }
 }
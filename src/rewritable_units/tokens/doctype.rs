@@ -0,0 +1,70 @@
+use lol_html::html_content::Doctype;
+use pyo3::prelude::*;
+
+use crate::rewritable_units::{Expirable, Expire, Replace};
+
+pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyDoctype>()?;
+    Ok(())
+}
+
+/// A [document type declaration] preamble.
+///
+/// Note that unlike other HTML content, `Doctype` can't be modified, but it can be removed.
+///
+/// [document type declaration]: https://developer.mozilla.org/en-US/docs/Glossary/Doctype
+// NOTE: lol_html only exposes `force_quirks` to its own integration tests, so it's missing here.
+#[pyclass(unsendable, name = "Doctype")]
+pub(crate) struct PyDoctype(Expirable<Doctype<'static>>);
+
+impl PyDoctype {
+    pub fn new(doctype: &'static mut Doctype<'static>) -> Self {
+        Self(Expirable::new(doctype))
+    }
+}
+
+impl Expire for PyDoctype {
+    fn expire(&mut self) {
+        self.0.expire()
+    }
+}
+
+impl Replace for PyDoctype {
+    fn remove(&mut self) -> PyResult<()> {
+        PyDoctype::remove(self)
+    }
+}
+
+#[pymethods]
+impl PyDoctype {
+    /// Returns the name of the doctype.
+    #[inline]
+    pub fn name(&self) -> PyResult<Option<String>> {
+        Ok(self.0.get()?.name())
+    }
+
+    /// Returns the public identifier of the doctype.
+    #[inline]
+    pub fn public_id(&self) -> PyResult<Option<String>> {
+        Ok(self.0.get()?.public_id())
+    }
+
+    /// Returns the system identifier of the doctype.
+    #[inline]
+    pub fn system_id(&self) -> PyResult<Option<String>> {
+        Ok(self.0.get()?.system_id())
+    }
+
+    /// Removes the doctype.
+    #[inline]
+    pub fn remove(&mut self) -> PyResult<()> {
+        self.0.get_mut()?.remove();
+        Ok(())
+    }
+
+    /// Returns `true` if the doctype has been removed.
+    #[inline]
+    pub fn removed(&self) -> PyResult<bool> {
+        Ok(self.0.get()?.removed())
+    }
+}
@@ -1,52 +1,118 @@
+use std::borrow::Cow;
+
 use lol_html::html_content::EndTag;
 use pyo3::prelude::*;
 
-use crate::rewritable_units::PyContentType;
+use crate::rewritable_units::{Expirable, Expire, PyContentType, Replace};
+use crate::rules::validate_tag_name;
 
 pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyEndTag>()?;
     Ok(())
 }
 
-#[pyclass]
-pub(crate) struct PyEndTag(&'static mut EndTag<'static>);
+/// A new name of the end tag, given either as `str` or as `bytes` in the document's encoding.
+#[derive(FromPyObject)]
+pub(crate) enum Name<'a> {
+    Str(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// An end tag, which remembers whether it has been removed or replaced, as lol_html doesn't
+/// expose that.
+#[pyclass(unsendable)]
+pub(crate) struct PyEndTag(Expirable<EndTag<'static>>, bool);
 
 impl PyEndTag {
     pub fn new(end: &'static mut EndTag<'static>) -> Self {
-        Self(end)
+        Self(Expirable::new(end), false)
+    }
+}
+
+impl Expire for PyEndTag {
+    fn expire(&mut self) {
+        self.0.expire()
+    }
+}
+
+impl Replace for PyEndTag {
+    fn remove(&mut self) -> PyResult<()> {
+        PyEndTag::remove(self)
+    }
+
+    fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        PyEndTag::replace(self, content, content_type)
     }
 }
 
 #[pymethods]
 impl PyEndTag {
     #[inline]
-    pub fn name(&self) -> String {
-        self.0.name()
+    pub fn name(&self) -> PyResult<String> {
+        Ok(self.0.get()?.name())
     }
 
+    /// Sets the name of the end tag.
+    ///
+    /// Raises `TagNameError` for the names `Element.set_tag_name` rejects, as lol_html writes the
+    /// name into the output as is.
     #[inline]
-    pub fn set_name(&mut self, _name: &[u8]) {
-        todo!()
+    pub fn set_name(&mut self, name: Name<'_>) -> PyResult<()> {
+        let end = self.0.get_mut()?;
+
+        match name {
+            Name::Str(name) => end.set_name_str(validate_tag_name(name)?),
+            Name::Bytes(name) => {
+                // NOTE: the forbidden characters are all ASCII, which bytes of ASCII-compatible
+                // encodings can't be mistaken for, so it's fine to validate the name lossily.
+                validate_tag_name(&String::from_utf8_lossy(name))?;
+                end.set_name(Cow::<[u8]>::Owned(name.to_vec()).into());
+            }
+        }
+
+        Ok(())
     }
 
     #[inline]
-    pub fn set_name_str(&mut self, name: String) {
-        self.0.set_name_str(name)
+    pub fn set_name_str(&mut self, name: &str) -> PyResult<()> {
+        self.0.get_mut()?.set_name_str(validate_tag_name(name)?);
+        Ok(())
     }
 
     #[inline]
-    pub fn before(&mut self, content: &str, content_type: PyContentType) {
-        self.0.before(content, content_type.into())
+    pub fn before(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.before(content, content_type.into());
+        Ok(())
     }
 
     #[inline]
-    pub fn after(&mut self, content: &str, content_type: PyContentType) {
-        self.0.after(content, content_type.into())
+    pub fn after(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.after(content, content_type.into());
+        Ok(())
+    }
+
+    /// Replaces the end tag with the `content`.
+    ///
+    /// Consequent calls to the method overwrite previous replacement content.
+    #[inline]
+    pub fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.replace(content, content_type.into());
+        self.1 = true;
+        Ok(())
     }
 
     /// Removes the end tag.
     #[inline]
-    pub fn remove(&mut self) {
-        self.0.remove()
+    pub fn remove(&mut self) -> PyResult<()> {
+        self.0.get_mut()?.remove();
+        self.1 = true;
+        Ok(())
+    }
+
+    /// `True` if the end tag has been replaced or removed.
+    #[getter]
+    pub fn removed(&self) -> PyResult<bool> {
+        self.0.get()?;
+        Ok(self.1)
     }
 }
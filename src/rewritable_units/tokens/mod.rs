@@ -1,4 +1,5 @@
 pub(crate) mod comments;
+pub(crate) mod doctype;
 pub(crate) mod end_tag;
 pub(crate) mod text_chunk;
 
@@ -8,5 +9,6 @@ pub(crate) fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     end_tag::register(py, m)?;
     text_chunk::register(py, m)?;
     comments::register(py, m)?;
+    doctype::register(py, m)?;
     Ok(())
 }
@@ -1,31 +1,113 @@
 use lol_html::html_content::{TextChunk, TextType};
+use pyo3::basic::CompareOp;
 use pyo3::prelude::*;
 
-use crate::rewritable_units::PyContentType;
+use crate::rewritable_units::{Expirable, Expire, PyContentType, Replace};
 
 pub(super) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyTextChunk>()?;
+    m.add_class::<PyTextType>()?;
     Ok(())
 }
 
-#[pyclass]
-pub(crate) struct PyTextType(TextType);
+/// A type of the text, which depends on the element the text is in.
+///
+/// The names of the text types are taken from the [HTML parsing specification].
+///
+/// [HTML parsing specification]: https://html.spec.whatwg.org/multipage/parsing.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[pyclass(name = "TextType")]
+pub(crate) enum PyTextType {
+    /// Text inside a `<plaintext>` element.
+    PlainText,
+    /// Text inside `<title>` and `<textarea>` elements.
+    RCData,
+    /// Text inside `<style>`, `<xmp>`, `<iframe>`, `<noembed>`, `<noframes>` and
+    /// `<noscript>` elements.
+    RawText,
+    /// Text inside a `<script>` element.
+    ScriptData,
+    /// Regular text.
+    Data,
+    /// Text inside a CDATA section.
+    CDataSection,
+}
+
+impl From<TextType> for PyTextType {
+    fn from(text_type: TextType) -> Self {
+        match text_type {
+            TextType::PlainText => PyTextType::PlainText,
+            TextType::RCData => PyTextType::RCData,
+            TextType::RawText => PyTextType::RawText,
+            TextType::ScriptData => PyTextType::ScriptData,
+            TextType::Data => PyTextType::Data,
+            TextType::CDataSection => PyTextType::CDataSection,
+        }
+    }
+}
+
+#[pymethods]
+impl PyTextType {
+    fn __repr__(&self) -> String {
+        format!("TextType.{:?}", self)
+    }
+
+    fn __hash__(&self) -> u64 {
+        *self as u64
+    }
+
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp) -> PyObject {
+        let py = other.py();
+
+        match (op, other.extract::<PyTextType>()) {
+            (CompareOp::Eq, Ok(other)) => (*self == other).into_py(py),
+            (CompareOp::Ne, Ok(other)) => (*self != other).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+}
 
+/// A text chunk, which in the node mode also carries the text of the whole text node.
 #[pyclass(unsendable)]
-pub(crate) struct PyTextChunk(&'static mut TextChunk<'static>);
+pub(crate) struct PyTextChunk(Expirable<TextChunk<'static>>, Option<String>);
 
 impl PyTextChunk {
     pub fn new(end: &'static mut TextChunk<'static>) -> Self {
-        Self(end)
+        Self(Expirable::new(end), None)
+    }
+
+    /// Wraps the last chunk of a text node, exposing the text of the whole node.
+    pub fn with_node_text(chunk: &'static mut TextChunk<'static>, text: &str) -> Self {
+        Self(Expirable::new(chunk), Some(text.to_owned()))
+    }
+}
+
+impl Expire for PyTextChunk {
+    fn expire(&mut self) {
+        self.0.expire()
+    }
+}
+
+impl Replace for PyTextChunk {
+    fn remove(&mut self) -> PyResult<()> {
+        PyTextChunk::remove(self)
+    }
+
+    fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        PyTextChunk::replace(self, content, content_type)
     }
 }
 
 #[pymethods]
 impl PyTextChunk {
-    /// Returns the textual content of the chunk.
+    /// Returns the textual content of the chunk, or of the whole text node in the node mode.
+    ///
+    /// The text is returned as it is in the source, i.e. character references aren't decoded.
     #[inline]
-    pub fn as_str(&self) -> &str {
-        self.0.as_str()
+    pub fn as_str(&self) -> PyResult<&str> {
+        let chunk = self.0.get()?;
+
+        Ok(self.1.as_deref().unwrap_or_else(|| chunk.as_str()))
     }
 
     /// Returns the type of the text in the chunk.
@@ -34,51 +116,55 @@ impl PyTextChunk {
     /// text and text inside a `<script>` element will have different types. Refer to [`TextType`]
     /// for more information about possible text types.
     #[inline]
-    pub fn text_type(&self) -> PyTextType {
-        PyTextType(self.0.text_type())
+    pub fn text_type(&self) -> PyResult<PyTextType> {
+        Ok(self.0.get()?.text_type().into())
     }
 
     /// Returns `true` if the chunk is last in a HTML text node.
     ///
     /// Note that last chunk can have empty textual content.
     #[inline]
-    pub fn last_in_text_node(&self) -> bool {
-        self.0.last_in_text_node()
+    pub fn last_in_text_node(&self) -> PyResult<bool> {
+        Ok(self.0.get()?.last_in_text_node())
     }
 
     /// Inserts `content` before the text chunk.
     ///
     /// Consequent calls to the method append `content` to the previously inserted content.
     #[inline]
-    pub fn before(&mut self, content: &str, content_type: PyContentType) {
-        self.0.before(content, content_type.into())
+    pub fn before(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.before(content, content_type.into());
+        Ok(())
     }
 
     /// Inserts `content` after the text chunk.
     ///
     /// Consequent calls to the method prepend `content` to the previously inserted content.
     #[inline]
-    pub fn after(&mut self, content: &str, content_type: PyContentType) {
-        self.0.after(content, content_type.into())
+    pub fn after(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.after(content, content_type.into());
+        Ok(())
     }
 
     /// Replaces the text chunk with the `content`.
     ///
     /// Consequent calls to the method overwrite previous replacement content.
     #[inline]
-    pub fn replace(&mut self, content: &str, content_type: PyContentType) {
-        self.0.after(content, content_type.into())
+    pub fn replace(&mut self, content: &str, content_type: PyContentType) -> PyResult<()> {
+        self.0.get_mut()?.replace(content, content_type.into());
+        Ok(())
     }
 
     /// Removes the text chunk.
     #[inline]
-    pub fn remove(&mut self) {
-        self.0.remove()
+    pub fn remove(&mut self) -> PyResult<()> {
+        self.0.get_mut()?.remove();
+        Ok(())
     }
 
     /// Returns `true` if the text chunk has been replaced or removed.
     #[inline]
-    pub fn removed(&self) -> bool {
-        self.0.removed()
+    pub fn removed(&self) -> PyResult<bool> {
+        Ok(self.0.get()?.removed())
     }
 }
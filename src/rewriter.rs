@@ -0,0 +1,553 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use encoding_rs::Encoding;
+use lol_html::{errors::RewritingError, HtmlRewriter, OutputSink, Settings};
+use pyo3::exceptions::{
+    PyOSError, PyRuntimeError, PyTypeError, PyUnicodeEncodeError, PyValueError,
+};
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyBytes, PyCFunction, PyDict, PyFloat, PyList, PyString, PyTuple, PyType,
+};
+use serde_json::{Map, Value};
+
+use crate::errors::{rewriting_error_to_pyerr, rule_set_error_to_pyerr};
+use crate::rule_set::{self, Format};
+use crate::settings::{
+    parse_selector, ElementHandler, PyDocumentContentHandler, PyElementContentHandler,
+    RewriterSettings,
+};
+
+pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyHtmlRewriter>()?;
+    m.add_class::<PyRewriter>()?;
+    Ok(())
+}
+
+/// Runs the whole `html` through a rewriter built with `settings`.
+///
+/// The GIL is released for the rewriting if it doesn't call into Python, so that documents can be
+/// rewritten by several threads in parallel.
+pub(crate) fn rewrite(
+    py: Python<'_>,
+    html: &[u8],
+    settings: &RewriterSettings,
+) -> PyResult<Vec<u8>> {
+    let result = if settings.calls_python() {
+        rewrite_with(settings.build(py), html)
+    } else {
+        py.allow_threads(|| rewrite_with(settings.build_native(), html))
+    };
+
+    result.map_err(|e| rewriting_error_to_pyerr(py, e))
+}
+
+/// Mirrors `lol_html::rewrite_str`, which enables ESI tags, unlike the streaming rewriter.
+fn rewrite_with(settings: Settings<'_, '_>, html: &[u8]) -> Result<Vec<u8>, RewritingError> {
+    let mut output = vec![];
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            enable_esi_tags: true,
+            ..settings
+        },
+        |c: &[u8]| output.extend_from_slice(c),
+    );
+
+    rewriter.write(html)?;
+    rewriter.end()?;
+
+    Ok(output)
+}
+
+/// Output sink that forwards rewritten chunks to a Python callable.
+///
+/// [`OutputSink`] can't report failures, so the first error raised by the callable is stored
+/// and re-raised by the rewriter once `write` or `end` returns.
+pub(crate) struct PyOutputSink {
+    callback: PyObject,
+    error: Rc<RefCell<Option<PyErr>>>,
+}
+
+impl OutputSink for PyOutputSink {
+    fn handle_chunk(&mut self, chunk: &[u8]) {
+        // NOTE: lol_html signals the end of the output with an empty chunk.
+        if chunk.is_empty() {
+            return;
+        }
+
+        let mut error = self.error.borrow_mut();
+
+        if error.is_none() {
+            Python::with_gil(|py| {
+                if let Err(e) = self.callback.call1(py, (PyBytes::new(py, chunk),)) {
+                    *error = Some(e);
+                }
+            })
+        }
+    }
+}
+
+/// HTML input given either as `bytes` in the document's encoding or as `str`.
+#[derive(FromPyObject)]
+pub(crate) enum Input<'a> {
+    Bytes(&'a [u8]),
+    Str(&'a str),
+}
+
+/// Encodes `string` into the document's `encoding`.
+///
+/// Raises `UnicodeEncodeError` for characters the encoding can't represent, which encoding_rs
+/// would replace with numeric character references.
+fn encode<'s>(encoding: &'static Encoding, string: &'s str) -> PyResult<Cow<'s, [u8]>> {
+    let (bytes, _, had_errors) = encoding.encode(string);
+
+    if !had_errors {
+        return Ok(bytes);
+    }
+
+    // NOTE: it's ok to unwrap here as there is at least one character that can't be encoded.
+    let (start, _) = string
+        .chars()
+        .enumerate()
+        .find(|(_, ch)| encoding.encode(ch.encode_utf8(&mut [0; 4])).2)
+        .unwrap();
+
+    Err(PyUnicodeEncodeError::new_err((
+        encoding.name(),
+        string.to_owned(),
+        start,
+        start + 1,
+        "character can't be represented in the document's encoding",
+    )))
+}
+
+/// A streaming HTML rewriter.
+///
+/// Input is fed with `write` and the rewriting is finalized with `end`. Rewritten output is
+/// passed to `output_sink` as `bytes` chunks in the document's `encoding` as soon as it is
+/// produced.
+#[pyclass(unsendable, name = "HtmlRewriter")]
+pub(crate) struct PyHtmlRewriter {
+    rewriter: Option<HtmlRewriter<'static, PyOutputSink>>,
+    encoding: &'static Encoding,
+    sink_error: Rc<RefCell<Option<PyErr>>>,
+}
+
+#[pymethods]
+impl PyHtmlRewriter {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[args(
+        output_sink,
+        "*",
+        encoding = "\"utf-8\"",
+        element_content_handlers = "Vec::new()",
+        document_content_handlers = "Vec::new()",
+        max_allowed_memory_usage = "None",
+        preallocated_parsing_buffer_size = "None",
+        strict = "true",
+        on_parsing_ambiguity = "None"
+    )]
+    fn __new__(
+        py: Python<'_>,
+        output_sink: PyObject,
+        encoding: &str,
+        element_content_handlers: Vec<ElementHandler>,
+        document_content_handlers: Vec<Py<PyDocumentContentHandler>>,
+        max_allowed_memory_usage: Option<usize>,
+        preallocated_parsing_buffer_size: Option<usize>,
+        strict: bool,
+        on_parsing_ambiguity: Option<PyObject>,
+    ) -> PyResult<Self> {
+        let settings = RewriterSettings::new(
+            element_content_handlers,
+            document_content_handlers,
+            encoding,
+            max_allowed_memory_usage,
+            preallocated_parsing_buffer_size,
+            strict,
+            on_parsing_ambiguity,
+        )?;
+
+        Ok(Self::new(py, &settings, output_sink))
+    }
+
+    /// Writes a chunk of input data to the rewriter.
+    ///
+    /// `bytes` are expected to be in the document's encoding, while `str` is encoded into it
+    /// before being written, raising `UnicodeEncodeError` if it can't be. Nothing is written then,
+    /// so the rewriter can still be used, while it can't be used anymore once the rewriting itself
+    /// fails, i.e. with a rewriting error, an exception raised by a content handler or by the
+    /// output sink.
+    fn write(&mut self, py: Python<'_>, chunk: Input<'_>) -> PyResult<()> {
+        let rewriter = self.rewriter.as_mut().ok_or_else(already_finished)?;
+        let result = match chunk {
+            Input::Bytes(bytes) => rewriter.write(bytes),
+            Input::Str(string) => rewriter.write(&encode(self.encoding, string)?),
+        };
+
+        self.check(py, result)
+    }
+
+    /// Finalizes the rewriting process.
+    ///
+    /// Should be called once the last chunk of the input is written.
+    fn end(&mut self, py: Python<'_>) -> PyResult<()> {
+        let rewriter = self.rewriter.take().ok_or_else(already_finished)?;
+        let result = rewriter.end();
+
+        self.check(py, result)
+    }
+}
+
+impl PyHtmlRewriter {
+    pub(crate) fn new(py: Python<'_>, settings: &RewriterSettings, output_sink: PyObject) -> Self {
+        let sink_error = Rc::new(RefCell::new(None));
+        let output_sink = PyOutputSink {
+            callback: output_sink,
+            error: Rc::clone(&sink_error),
+        };
+
+        Self {
+            rewriter: Some(HtmlRewriter::new(settings.build(py), output_sink)),
+            encoding: settings.encoding.into(),
+            sink_error,
+        }
+    }
+
+    /// Converts the outcome of a rewriter call into a Python result, dropping the rewriter on
+    /// failure: lol_html panics on any use of a rewriter after an error.
+    fn check(&mut self, py: Python<'_>, result: Result<(), RewritingError>) -> PyResult<()> {
+        let result = result
+            .map_err(|e| rewriting_error_to_pyerr(py, e))
+            .and_then(|()| match self.sink_error.borrow_mut().take() {
+                Some(e) => Err(e),
+                None => Ok(()),
+            });
+
+        if result.is_err() {
+            self.rewriter = None;
+        }
+
+        result
+    }
+}
+
+fn already_finished() -> PyErr {
+    PyRuntimeError::new_err("The rewriter has already been ended or has failed.")
+}
+
+/// A reusable rewriter configuration.
+///
+/// Content handlers, selectors and settings are validated once on construction and can then be
+/// used for any number of rewrites. More content handlers can be registered with decorators,
+/// e.g. `@rewriter.element("a[href]")`.
+#[pyclass(name = "Rewriter")]
+pub(crate) struct PyRewriter {
+    settings: RewriterSettings,
+}
+
+#[pymethods]
+impl PyRewriter {
+    #[new]
+    #[args(
+        "*",
+        element_content_handlers = "Vec::new()",
+        document_content_handlers = "Vec::new()",
+        encoding = "\"utf-8\"",
+        max_allowed_memory_usage = "None",
+        preallocated_parsing_buffer_size = "None",
+        strict = "true",
+        on_parsing_ambiguity = "None"
+    )]
+    fn __new__(
+        element_content_handlers: Vec<ElementHandler>,
+        document_content_handlers: Vec<Py<PyDocumentContentHandler>>,
+        encoding: &str,
+        max_allowed_memory_usage: Option<usize>,
+        preallocated_parsing_buffer_size: Option<usize>,
+        strict: bool,
+        on_parsing_ambiguity: Option<PyObject>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            settings: RewriterSettings::new(
+                element_content_handlers,
+                document_content_handlers,
+                encoding,
+                max_allowed_memory_usage,
+                preallocated_parsing_buffer_size,
+                strict,
+                on_parsing_ambiguity,
+            )?,
+        })
+    }
+
+    /// Rewrites the whole `html` document.
+    ///
+    /// Returns `bytes` in the document's encoding for `bytes` input and `str` for `str` input.
+    /// `str` input is encoded into the document's encoding, raising `UnicodeEncodeError` if it
+    /// can't be.
+    fn rewrite(&self, py: Python<'_>, html: Input<'_>) -> PyResult<PyObject> {
+        let encoding: &'static Encoding = self.settings.encoding.into();
+
+        Ok(match html {
+            Input::Bytes(bytes) => PyBytes::new(py, &rewrite(py, bytes, &self.settings)?).into(),
+            Input::Str(string) => {
+                let output = rewrite(py, &encode(encoding, string)?, &self.settings)?;
+
+                encoding.decode_without_bom_handling(&output).0.into_py(py)
+            }
+        })
+    }
+
+    /// Creates a rewriter applying the native rules of a rule set.
+    ///
+    /// `rules` is a path to a JSON, TOML or YAML file (told apart by the extension), or the rule
+    /// set itself as a `dict` or a `list`. A rule set is a mapping with the `rules` list, or just
+    /// the list, where every rule names the `selector`, the `action` and its arguments, e.g.:
+    ///
+    /// ```toml
+    /// [[rules]]
+    /// selector = "a[href^='http']"
+    /// action = "set_attribute"
+    /// name = "rel"
+    /// value = "noopener"
+    /// ```
+    ///
+    /// Actions are `set_attribute` (`name`, `value`), `remove_attribute` (`name`),
+    /// `remove_element`, `rename_tag` (`name`), `insert_before`, `insert_after`, `prepend`,
+    /// `append`, `replace_with` (`content`, `content_type` of `"text"` or `"html"`) and `unwrap`.
+    ///
+    /// Raises `RuleSetError` naming the rule `index` and the `field` at fault for an invalid rule
+    /// set. Keyword arguments are passed to `Rewriter`, the rules are applied after any of the
+    /// given `element_content_handlers`.
+    #[classmethod]
+    #[args(rules, kwargs = "**")]
+    fn from_rules(
+        cls: &PyType,
+        rules: &PyAny,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<Py<PyRewriter>> {
+        let py = cls.py();
+        let compiled = if rules.is_instance_of::<PyDict>()? || rules.is_instance_of::<PyList>()? {
+            rule_set::compile(&to_json_value(rules)?)
+        } else {
+            let path: PathBuf = rules.extract()?;
+            let format = Format::from_path(&path).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Can't tell the format of `{}`, expected a `.json`, `.toml`, `.yaml` or \
+                     `.yml` file.",
+                    path.display()
+                ))
+            })?;
+
+            let source = fs::read_to_string(&path).map_err(|e| io_error_with_path(py, e, &path))?;
+
+            rule_set::parse(&source, format)
+        };
+        let compiled = match compiled {
+            Ok(compiled) => compiled,
+            Err(e) => return Err(rule_set_error_to_pyerr(py, e)?),
+        };
+
+        let rewriter: Py<PyRewriter> = cls.call((), kwargs)?.extract()?;
+
+        rewriter
+            .borrow_mut(py)
+            .settings
+            .element_content_handlers
+            .extend(compiled.into_iter().map(ElementHandler::Native));
+
+        Ok(rewriter)
+    }
+
+    /// Creates a streaming rewriter that passes the rewritten output to `output_sink`.
+    fn stream(&self, py: Python<'_>, output_sink: PyObject) -> PyHtmlRewriter {
+        PyHtmlRewriter::new(py, &self.settings, output_sink)
+    }
+
+    /// Decorator registering the `element` handler for the `selector`.
+    ///
+    /// Keyword arguments are passed to `ElementContentHandler`.
+    #[args(selector, kwargs = "**")]
+    fn element(slf: &PyCell<Self>, selector: &str, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+        handler_decorator(slf, Some(selector), "element", kwargs)
+    }
+
+    /// Decorator registering the `end_tag` handler for the `selector`.
+    ///
+    /// Keyword arguments are passed to `ElementContentHandler`.
+    #[args(selector, kwargs = "**")]
+    fn end_tag(slf: &PyCell<Self>, selector: &str, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+        handler_decorator(slf, Some(selector), "end_tag", kwargs)
+    }
+
+    /// Decorator registering the `text` handler for the `selector`, or for the whole document if
+    /// the selector isn't given.
+    ///
+    /// Keyword arguments are passed to the content handler, e.g. `text_mode="node"`.
+    #[args(selector = "None", kwargs = "**")]
+    fn text(
+        slf: &PyCell<Self>,
+        selector: Option<&str>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        handler_decorator(slf, selector, "text", kwargs)
+    }
+
+    /// Decorator registering the `comments` handler for the `selector`, or for the whole document
+    /// if the selector isn't given.
+    #[args(selector = "None", kwargs = "**")]
+    fn comments(
+        slf: &PyCell<Self>,
+        selector: Option<&str>,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<PyObject> {
+        handler_decorator(slf, selector, "comments", kwargs)
+    }
+
+    /// Decorator registering the document `doctype` handler.
+    fn doctype(&mut self, py: Python<'_>, handler: PyObject) -> PyResult<PyObject> {
+        self.add_handler(py, None, "doctype", &handler, None)?;
+        Ok(handler)
+    }
+
+    /// Decorator registering the document `end` handler.
+    fn document_end(&mut self, py: Python<'_>, handler: PyObject) -> PyResult<PyObject> {
+        self.add_handler(py, None, "end", &handler, None)?;
+        Ok(handler)
+    }
+}
+
+impl PyRewriter {
+    /// Registers a content handler built from `kwargs`, with `handler` as its `kind` handler.
+    ///
+    /// Document content handlers are registered if no `selector` is given.
+    fn add_handler(
+        &mut self,
+        py: Python<'_>,
+        selector: Option<&str>,
+        kind: &str,
+        handler: &PyObject,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<()> {
+        let kwargs = match kwargs {
+            Some(kwargs) => kwargs.copy()?,
+            None => PyDict::new(py),
+        };
+
+        kwargs.set_item(kind, handler)?;
+
+        match selector {
+            Some(selector) => self
+                .settings
+                .element_content_handlers
+                .push(ElementHandler::Python(
+                    py.get_type::<PyElementContentHandler>()
+                        .call((selector,), Some(kwargs))?
+                        .extract()?,
+                )),
+            None => self.settings.document_content_handlers.push(
+                py.get_type::<PyDocumentContentHandler>()
+                    .call((), Some(kwargs))?
+                    .extract()?,
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts an error reading the file at `path` into `OSError`, or the subclass matching the
+/// error code, with the path as the `filename` of the exception.
+fn io_error_with_path(py: Python<'_>, e: io::Error, path: &Path) -> PyErr {
+    let code = match e.raw_os_error() {
+        Some(code) => code,
+        None => return PyOSError::new_err(format!("{}: {}", path.display(), e)),
+    };
+
+    match py
+        .import("os")
+        .and_then(|os| os.call_method1("strerror", (code,)))
+    {
+        Ok(message) => PyOSError::new_err((code, message.to_object(py), path.to_owned())),
+        Err(e) => e,
+    }
+}
+
+/// Converts a rule set given as Python objects into the value it'd be parsed into from a file.
+fn to_json_value(obj: &PyAny) -> PyResult<Value> {
+    if obj.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(value) = obj.downcast::<PyBool>() {
+        Ok(Value::Bool(value.is_true()))
+    } else if let Ok(value) = obj.downcast::<PyString>() {
+        Ok(Value::String(value.to_str()?.to_owned()))
+    } else if let Ok(value) = obj.extract::<i64>() {
+        Ok(Value::from(value))
+    } else if let Ok(value) = obj.downcast::<PyFloat>() {
+        Ok(Value::from(value.value()))
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        dict.iter()
+            .map(|(key, value)| Ok((key.extract()?, to_json_value(value)?)))
+            .collect::<PyResult<Map<_, _>>>()
+            .map(Value::Object)
+    } else if obj.is_instance_of::<PyList>()? || obj.is_instance_of::<PyTuple>()? {
+        obj.iter()?
+            .map(|item| to_json_value(item?))
+            .collect::<PyResult<_>>()
+            .map(Value::Array)
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "Rule sets can only contain `dict`, `list`, `str`, `int`, `float`, `bool` and \
+             `None`, not `{}`.",
+            obj.get_type().name()?
+        )))
+    }
+}
+
+/// Builds a decorator registering the decorated function as the `kind` handler with
+/// [`PyRewriter::add_handler`] and returning the function as is.
+fn handler_decorator(
+    rewriter: &PyCell<PyRewriter>,
+    selector: Option<&str>,
+    kind: &'static str,
+    kwargs: Option<&PyDict>,
+) -> PyResult<PyObject> {
+    let py = rewriter.py();
+
+    // NOTE: the selector is validated upfront, so that invalid rules fail where they are defined.
+    if let Some(selector) = selector {
+        parse_selector(selector)?;
+    }
+
+    let rewriter: Py<PyRewriter> = rewriter.into();
+    let selector = selector.map(str::to_owned);
+    let kwargs: Option<Py<PyDict>> = kwargs.map(|kwargs| kwargs.into());
+
+    let decorator = PyCFunction::new_closure(
+        move |args: &PyTuple, _kwargs: Option<&PyDict>| -> PyResult<PyObject> {
+            let py = args.py();
+            let (handler,): (PyObject,) = args.extract()?;
+
+            rewriter.borrow_mut(py).add_handler(
+                py,
+                selector.as_deref(),
+                kind,
+                &handler,
+                kwargs.as_ref().map(|kwargs| kwargs.as_ref(py)),
+            )?;
+
+            Ok(handler)
+        },
+        py,
+    )?;
+
+    Ok(decorator.into())
+}
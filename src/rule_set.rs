@@ -0,0 +1,262 @@
+use std::{fmt, path::Path};
+
+use serde_json::{Map, Value};
+
+use crate::rewritable_units::PyContentType;
+use crate::rules::{validate_attribute_name, validate_tag_name, Action, NativeRule};
+
+/// Actions of the rule set schema, with the fields they take besides `selector` and `action`.
+const ACTIONS: [(&str, &[&str]); 10] = [
+    ("set_attribute", &["name", "value"]),
+    ("remove_attribute", &["name"]),
+    ("remove_element", &[]),
+    ("rename_tag", &["name"]),
+    ("insert_before", &["content", "content_type"]),
+    ("insert_after", &["content", "content_type"]),
+    ("prepend", &["content", "content_type"]),
+    ("append", &["content", "content_type"]),
+    ("replace_with", &["content", "content_type"]),
+    ("unwrap", &[]),
+];
+
+/// Format of a rule set file.
+#[derive(Clone, Copy)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    /// Detects the format by the file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    fn parse(self, source: &str) -> Result<Value, String> {
+        match self {
+            Format::Json => serde_json::from_str(source).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(source).map_err(|e| e.to_string()),
+            Format::Yaml => serde_yaml_ng::from_str(source).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// An invalid rule set, naming the rule and the field at fault.
+#[derive(Debug)]
+pub struct RuleSetError {
+    /// Index of the rule in the `rules` list, if the error is in a rule.
+    pub index: Option<usize>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl RuleSetError {
+    fn new(index: Option<usize>, field: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            index,
+            field: field.map(str::to_owned),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RuleSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.index, &self.field) {
+            (Some(index), Some(field)) => write!(f, "rules[{}].{}: ", index, field)?,
+            (Some(index), None) => write!(f, "rules[{}]: ", index)?,
+            (None, Some(field)) => write!(f, "{}: ", field)?,
+            (None, None) => (),
+        }
+
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RuleSetError {}
+
+/// Parses a rule set `source` in the given `format`, see [`compile`] for the schema.
+pub fn parse(source: &str, format: Format) -> Result<Vec<NativeRule>, RuleSetError> {
+    let value = format
+        .parse(source)
+        .map_err(|e| RuleSetError::new(None, None, e))?;
+
+    compile(&value)
+}
+
+/// Compiles a rule set into native rules.
+///
+/// A rule set is a mapping with the `rules` list, or just the list. Every rule is a mapping with
+/// the CSS `selector` of the elements to rewrite, the `action` to take, and the fields of the
+/// action:
+///
+/// | `action`           | fields                      |
+/// |--------------------|-----------------------------|
+/// | `set_attribute`    | `name`, `value`             |
+/// | `remove_attribute` | `name`                      |
+/// | `remove_element`   |                             |
+/// | `rename_tag`       | `name`                      |
+/// | `insert_before`    | `content`, `content_type`   |
+/// | `insert_after`     | `content`, `content_type`   |
+/// | `prepend`          | `content`, `content_type`   |
+/// | `append`           | `content`, `content_type`   |
+/// | `replace_with`     | `content`, `content_type`   |
+/// | `unwrap`           |                             |
+///
+/// All the fields are strings and are required, `content_type` is either `text` or `html`.
+/// Rules are applied in the order they are listed.
+pub(crate) fn compile(value: &Value) -> Result<Vec<NativeRule>, RuleSetError> {
+    let rules = match value {
+        Value::Array(rules) => rules,
+        Value::Object(rule_set) => {
+            if let Some(field) = rule_set.keys().find(|&field| field != "rules") {
+                return Err(RuleSetError::new(None, Some(field), "Unknown field."));
+            }
+
+            match rule_set.get("rules") {
+                Some(Value::Array(rules)) => rules,
+                Some(_) => {
+                    return Err(RuleSetError::new(
+                        None,
+                        Some("rules"),
+                        "Expected a list of rules.",
+                    ))
+                }
+                None => {
+                    return Err(RuleSetError::new(
+                        None,
+                        Some("rules"),
+                        "Missing required field.",
+                    ))
+                }
+            }
+        }
+        _ => {
+            return Err(RuleSetError::new(
+                None,
+                None,
+                "Expected a mapping with the `rules` list or a list of rules.",
+            ))
+        }
+    };
+
+    rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| compile_rule(index, rule))
+        .collect()
+}
+
+fn compile_rule(index: usize, rule: &Value) -> Result<NativeRule, RuleSetError> {
+    let rule = match rule {
+        Value::Object(rule) => Fields { index, rule },
+        _ => return Err(RuleSetError::new(Some(index), None, "Expected a mapping.")),
+    };
+
+    let selector = rule.str("selector")?;
+    let action = rule.str("action")?;
+    let fields = match ACTIONS.iter().find(|(name, _)| *name == action) {
+        Some((_, fields)) => fields,
+        None => {
+            return Err(rule.error(
+                "action",
+                format!(
+                    "Unknown action `{}`, expected one of: {}.",
+                    action,
+                    ACTIONS
+                        .iter()
+                        .map(|(name, _)| format!("`{}`", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ))
+        }
+    };
+
+    if let Some(field) = rule
+        .rule
+        .keys()
+        .find(|&field| field != "selector" && field != "action" && !fields.contains(&&**field))
+    {
+        return Err(rule.error(field, format!("Unknown field of the `{}` action.", action)));
+    }
+
+    let action = match action {
+        "set_attribute" => Action::SetAttribute {
+            name: rule.attribute_name("name")?,
+            value: rule.str("value")?.to_owned(),
+        },
+        "remove_attribute" => Action::RemoveAttribute {
+            name: rule.str("name")?.to_owned(),
+        },
+        "remove_element" => Action::RemoveElement,
+        "rename_tag" => Action::RenameTag {
+            name: rule.tag_name("name")?,
+        },
+        "insert_before" => Action::InsertBefore {
+            content: rule.str("content")?.to_owned(),
+            content_type: rule.content_type()?,
+        },
+        "insert_after" => Action::InsertAfter {
+            content: rule.str("content")?.to_owned(),
+            content_type: rule.content_type()?,
+        },
+        "prepend" => Action::Prepend {
+            content: rule.str("content")?.to_owned(),
+            content_type: rule.content_type()?,
+        },
+        "append" => Action::Append {
+            content: rule.str("content")?.to_owned(),
+            content_type: rule.content_type()?,
+        },
+        "replace_with" => Action::ReplaceWith {
+            content: rule.str("content")?.to_owned(),
+            content_type: rule.content_type()?,
+        },
+        _ => Action::Unwrap,
+    };
+
+    NativeRule::new(selector, action).map_err(|e| rule.error("selector", e.to_string()))
+}
+
+/// Fields of the rule with `index`.
+struct Fields<'r> {
+    index: usize,
+    rule: &'r Map<String, Value>,
+}
+
+impl<'r> Fields<'r> {
+    fn error(&self, field: &str, message: impl Into<String>) -> RuleSetError {
+        RuleSetError::new(Some(self.index), Some(field), message)
+    }
+
+    fn str(&self, field: &str) -> Result<&'r str, RuleSetError> {
+        match self.rule.get(field) {
+            Some(Value::String(value)) => Ok(value),
+            Some(_) => Err(self.error(field, "Expected a string.")),
+            None => Err(self.error(field, "Missing required field.")),
+        }
+    }
+
+    fn attribute_name(&self, field: &str) -> Result<String, RuleSetError> {
+        validate_attribute_name(self.str(field)?).map_err(|e| self.error(field, e.to_string()))
+    }
+
+    fn tag_name(&self, field: &str) -> Result<String, RuleSetError> {
+        validate_tag_name(self.str(field)?).map_err(|e| self.error(field, e.to_string()))
+    }
+
+    fn content_type(&self) -> Result<PyContentType, RuleSetError> {
+        match self.str("content_type")? {
+            "text" => Ok(PyContentType::Text),
+            "html" => Ok(PyContentType::Html),
+            _ => Err(self.error("content_type", "Expected `text` or `html`.")),
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use std::{borrow::Cow, error::Error};
+
+use lol_html::{html_content::Element, ElementContentHandlers, Selector};
+use pyo3::prelude::*;
+use thiserror::Error;
+
+use crate::errors::{AttributeNameError, SelectorError};
+use crate::rewritable_units::{element::TagNameError, PyContentType};
+
+pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyRule>()?;
+    m.add_class::<PySetAttribute>()?;
+    m.add_class::<PyRemoveAttribute>()?;
+    m.add_class::<PyRemoveElement>()?;
+    m.add_class::<PyRenameTag>()?;
+    m.add_class::<PyInsertBefore>()?;
+    m.add_class::<PyInsertAfter>()?;
+    m.add_class::<PyPrepend>()?;
+    m.add_class::<PyAppend>()?;
+    m.add_class::<PyReplaceWith>()?;
+    m.add_class::<PyUnwrap>()?;
+    Ok(())
+}
+
+/// An error in the definition of a native rule.
+///
+/// Doesn't need the GIL, unlike Python exceptions it's converted to.
+#[derive(Debug, Error)]
+pub(crate) enum RuleError {
+    #[error("Invalid selector `{selector}`: {error} ({error:?})")]
+    Selector {
+        selector: String,
+        error: lol_html::errors::SelectorError,
+    },
+    #[error("{0}")]
+    AttributeName(String),
+    #[error("{0}")]
+    TagName(String),
+}
+
+impl From<RuleError> for PyErr {
+    fn from(e: RuleError) -> Self {
+        match e {
+            RuleError::Selector { .. } => SelectorError::new_err(e.to_string()),
+            RuleError::AttributeName(_) => AttributeNameError::new_err(e.to_string()),
+            RuleError::TagName(_) => TagNameError::new_err(e.to_string()),
+        }
+    }
+}
+
+/// Parses a CSS selector, naming the selector and the kind of error on failure.
+pub(crate) fn compile_selector(selector: &str) -> Result<Selector, RuleError> {
+    selector.parse().map_err(|error| RuleError::Selector {
+        selector: selector.to_owned(),
+        error,
+    })
+}
+
+/// What a native rule does with the matched elements.
+#[derive(Clone)]
+pub(crate) enum Action {
+    SetAttribute {
+        name: String,
+        value: String,
+    },
+    RemoveAttribute {
+        name: String,
+    },
+    RemoveElement,
+    RenameTag {
+        name: String,
+    },
+    InsertBefore {
+        content: String,
+        content_type: PyContentType,
+    },
+    InsertAfter {
+        content: String,
+        content_type: PyContentType,
+    },
+    Prepend {
+        content: String,
+        content_type: PyContentType,
+    },
+    Append {
+        content: String,
+        content_type: PyContentType,
+    },
+    ReplaceWith {
+        content: String,
+        content_type: PyContentType,
+    },
+    Unwrap,
+}
+
+impl Action {
+    fn apply(&self, el: &mut Element) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            Action::SetAttribute { name, value } => el.set_attribute(name, value)?,
+            Action::RemoveAttribute { name } => el.remove_attribute(name),
+            Action::RemoveElement => el.remove(),
+            Action::RenameTag { name } => el.set_tag_name(name)?,
+            Action::InsertBefore {
+                content,
+                content_type,
+            } => el.before(content, (*content_type).into()),
+            Action::InsertAfter {
+                content,
+                content_type,
+            } => el.after(content, (*content_type).into()),
+            Action::Prepend {
+                content,
+                content_type,
+            } => el.prepend(content, (*content_type).into()),
+            Action::Append {
+                content,
+                content_type,
+            } => el.append(content, (*content_type).into()),
+            Action::ReplaceWith {
+                content,
+                content_type,
+            } => el.replace(content, (*content_type).into()),
+            Action::Unwrap => el.remove_and_keep_content(),
+        }
+
+        Ok(())
+    }
+}
+
+/// A rule applying an [`Action`] to the elements matched by the selector.
+///
+/// Rules are plain Rust data, so rewrites using only them don't need the GIL.
+#[derive(Clone)]
+pub struct NativeRule {
+    pub(crate) selector: String,
+    pub(crate) compiled_selector: Selector,
+    pub(crate) action: Action,
+}
+
+impl NativeRule {
+    pub(crate) fn new(selector: &str, action: Action) -> Result<Self, RuleError> {
+        Ok(Self {
+            selector: selector.to_owned(),
+            compiled_selector: compile_selector(selector)?,
+            action,
+        })
+    }
+
+    pub fn as_element_content_handlers<'h>(
+        &self,
+    ) -> (Cow<'h, Selector>, ElementContentHandlers<'h>) {
+        let action = self.action.clone();
+        let handlers =
+            ElementContentHandlers::default().element(move |el: &mut Element| action.apply(el));
+
+        (Cow::Owned(self.compiled_selector.clone()), handlers)
+    }
+}
+
+/// Validates an attribute name the way lol_html does, so that invalid rules fail where they are
+/// defined rather than in the middle of the rewriting.
+pub(crate) fn validate_attribute_name(name: &str) -> Result<String, RuleError> {
+    if name.is_empty() {
+        Err(RuleError::AttributeName(
+            "Attribute name can't be empty.".to_owned(),
+        ))
+    } else if let Some(ch) = name
+        .chars()
+        .find(|&ch| matches!(ch, ' ' | '\n' | '\r' | '\t' | '\x0C' | '/' | '>' | '='))
+    {
+        Err(RuleError::AttributeName(format!(
+            "`{}` character is forbidden in the attribute name.",
+            ch.escape_default()
+        )))
+    } else {
+        Ok(name.to_owned())
+    }
+}
+
+/// Validates a tag name the way lol_html does, see [`validate_attribute_name`].
+pub(crate) fn validate_tag_name(name: &str) -> Result<String, RuleError> {
+    if !name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        Err(RuleError::TagName(
+            "Tag name should start with an ASCII alphabetical character.".to_owned(),
+        ))
+    } else if let Some(ch) = name
+        .chars()
+        .find(|&ch| matches!(ch, ' ' | '\n' | '\r' | '\t' | '\x0C' | '/' | '>'))
+    {
+        Err(RuleError::TagName(format!(
+            "`{}` character is forbidden in the tag name.",
+            ch.escape_default()
+        )))
+    } else {
+        Ok(name.to_owned())
+    }
+}
+
+/// The base class of native rules.
+///
+/// Native rules are executed by the rewriter itself without calling into Python and can be mixed
+/// with `ElementContentHandler`s in `element_content_handlers`.
+#[pyclass(subclass, name = "Rule")]
+pub(crate) struct PyRule {
+    pub(crate) rule: NativeRule,
+}
+
+impl PyRule {
+    fn new(selector: &str, action: Action) -> PyResult<Self> {
+        Ok(Self {
+            rule: NativeRule::new(selector, action)?,
+        })
+    }
+}
+
+#[pymethods]
+impl PyRule {
+    /// The CSS selector of the rule.
+    #[getter]
+    fn selector(&self) -> &str {
+        &self.rule.selector
+    }
+}
+
+/// Sets the attribute with `name` to `value` on the matched elements.
+#[pyclass(extends = PyRule, name = "SetAttribute")]
+pub(crate) struct PySetAttribute;
+
+#[pymethods]
+impl PySetAttribute {
+    #[new]
+    fn __new__(selector: &str, name: &str, value: &str) -> PyResult<(Self, PyRule)> {
+        let action = Action::SetAttribute {
+            name: validate_attribute_name(name)?,
+            value: value.to_owned(),
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Removes the attribute with `name` from the matched elements.
+#[pyclass(extends = PyRule, name = "RemoveAttribute")]
+pub(crate) struct PyRemoveAttribute;
+
+#[pymethods]
+impl PyRemoveAttribute {
+    #[new]
+    fn __new__(selector: &str, name: &str) -> PyResult<(Self, PyRule)> {
+        let action = Action::RemoveAttribute {
+            name: name.to_owned(),
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Removes the matched elements together with their content.
+#[pyclass(extends = PyRule, name = "RemoveElement")]
+pub(crate) struct PyRemoveElement;
+
+#[pymethods]
+impl PyRemoveElement {
+    #[new]
+    fn __new__(selector: &str) -> PyResult<(Self, PyRule)> {
+        Ok((Self, PyRule::new(selector, Action::RemoveElement)?))
+    }
+}
+
+/// Renames the matched elements to `name`.
+#[pyclass(extends = PyRule, name = "RenameTag")]
+pub(crate) struct PyRenameTag;
+
+#[pymethods]
+impl PyRenameTag {
+    #[new]
+    fn __new__(selector: &str, name: &str) -> PyResult<(Self, PyRule)> {
+        let action = Action::RenameTag {
+            name: validate_tag_name(name)?,
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Inserts `content` before the matched elements.
+#[pyclass(extends = PyRule, name = "InsertBefore")]
+pub(crate) struct PyInsertBefore;
+
+#[pymethods]
+impl PyInsertBefore {
+    #[new]
+    fn __new__(
+        selector: &str,
+        content: String,
+        content_type: PyContentType,
+    ) -> PyResult<(Self, PyRule)> {
+        let action = Action::InsertBefore {
+            content,
+            content_type,
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Inserts `content` after the matched elements.
+#[pyclass(extends = PyRule, name = "InsertAfter")]
+pub(crate) struct PyInsertAfter;
+
+#[pymethods]
+impl PyInsertAfter {
+    #[new]
+    fn __new__(
+        selector: &str,
+        content: String,
+        content_type: PyContentType,
+    ) -> PyResult<(Self, PyRule)> {
+        let action = Action::InsertAfter {
+            content,
+            content_type,
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Inserts `content` right after the start tag of the matched elements.
+#[pyclass(extends = PyRule, name = "Prepend")]
+pub(crate) struct PyPrepend;
+
+#[pymethods]
+impl PyPrepend {
+    #[new]
+    fn __new__(
+        selector: &str,
+        content: String,
+        content_type: PyContentType,
+    ) -> PyResult<(Self, PyRule)> {
+        let action = Action::Prepend {
+            content,
+            content_type,
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Inserts `content` right before the end tag of the matched elements.
+#[pyclass(extends = PyRule, name = "Append")]
+pub(crate) struct PyAppend;
+
+#[pymethods]
+impl PyAppend {
+    #[new]
+    fn __new__(
+        selector: &str,
+        content: String,
+        content_type: PyContentType,
+    ) -> PyResult<(Self, PyRule)> {
+        let action = Action::Append {
+            content,
+            content_type,
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Replaces the matched elements and their content with `content`.
+#[pyclass(extends = PyRule, name = "ReplaceWith")]
+pub(crate) struct PyReplaceWith;
+
+#[pymethods]
+impl PyReplaceWith {
+    #[new]
+    fn __new__(
+        selector: &str,
+        content: String,
+        content_type: PyContentType,
+    ) -> PyResult<(Self, PyRule)> {
+        let action = Action::ReplaceWith {
+            content,
+            content_type,
+        };
+
+        Ok((Self, PyRule::new(selector, action)?))
+    }
+}
+
+/// Removes the start and end tags of the matched elements, keeping their content.
+#[pyclass(extends = PyRule, name = "Unwrap")]
+pub(crate) struct PyUnwrap;
+
+#[pymethods]
+impl PyUnwrap {
+    #[new]
+    fn __new__(selector: &str) -> PyResult<(Self, PyRule)> {
+        Ok((Self, PyRule::new(selector, Action::Unwrap)?))
+    }
+}
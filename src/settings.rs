@@ -1,16 +1,29 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, error::Error, sync::Arc};
 
+use encoding_rs::Encoding;
 use lol_html::{
-    html_content::{Comment, DocumentEnd, Element, TextChunk},
-    DocumentContentHandlers, ElementContentHandlers, Selector,
+    html_content::{Comment, ContentType, Doctype, DocumentEnd, Element, EndTag, TextChunk},
+    AsciiCompatibleEncoding, DocumentContentHandlers, ElementContentHandlers, MemorySettings,
+    Selector, Settings,
 };
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use thiserror::Error;
 
+use crate::ambiguity::{self, EndTagTracker};
+use crate::errors::{EncodingError, MemoryLimitExceededError};
 use crate::rewritable_units::{
+    call_with_unit, call_with_unit_and_arg,
     document_end::PyDocumentEnd,
-    element::PyElement,
-    tokens::{comments::PyComment, text_chunk::PyTextChunk},
+    element::{set_end_tag_handler, PyElement},
+    tokens::{
+        comments::PyComment,
+        doctype::PyDoctype,
+        end_tag::PyEndTag,
+        text_chunk::{PyTextChunk, PyTextType},
+    },
 };
+use crate::rules::{compile_selector, NativeRule, PyRule};
 
 pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyElementContentHandler>()?;
@@ -18,138 +31,601 @@ pub(crate) fn register(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     Ok(())
 }
 
-#[pyclass(name = "ElementContentHandler")]
+/// An invalid setting of the rewriter.
+///
+/// Doesn't need the GIL, unlike Python exceptions it's converted to, so that the `lolhtml`
+/// binary validates its options the same way.
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Unknown encoding `{0}`.")]
+    UnknownEncoding(String),
+    #[error("Encoding `{0}` is not ASCII-compatible and can't be used by the rewriter.")]
+    NonAsciiCompatibleEncoding(&'static str),
+    #[error(
+        "`preallocated_parsing_buffer_size` ({preallocated_parsing_buffer_size}) exceeds \
+         `max_allowed_memory_usage` ({max_allowed_memory_usage})."
+    )]
+    PreallocatedBufferTooLarge {
+        preallocated_parsing_buffer_size: usize,
+        max_allowed_memory_usage: usize,
+    },
+}
+
+impl From<SettingsError> for PyErr {
+    fn from(e: SettingsError) -> Self {
+        match e {
+            SettingsError::UnknownEncoding(_) | SettingsError::NonAsciiCompatibleEncoding(_) => {
+                EncodingError::new_err(e.to_string())
+            }
+            SettingsError::PreallocatedBufferTooLarge { .. } => {
+                PyValueError::new_err(e.to_string())
+            }
+        }
+    }
+}
+
+/// Looks up the document encoding by its [label].
+///
+/// Only ASCII-compatible encodings are supported by lol_html.
+///
+/// [label]: https://encoding.spec.whatwg.org/#names-and-labels
+pub fn parse_encoding(label: &str) -> Result<AsciiCompatibleEncoding, SettingsError> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| SettingsError::UnknownEncoding(label.to_owned()))?;
+
+    AsciiCompatibleEncoding::new(encoding)
+        .ok_or_else(|| SettingsError::NonAsciiCompatibleEncoding(encoding.name()))
+}
+
+/// Validates the memory settings, falling back to lol_html defaults for the omitted values.
+///
+/// The default preallocated buffer is shrunk to fit into a smaller memory limit, while a buffer
+/// given explicitly has to fit into it, as lol_html panics otherwise.
+pub fn memory_settings(
+    max_allowed_memory_usage: Option<usize>,
+    preallocated_parsing_buffer_size: Option<usize>,
+) -> Result<MemorySettings, SettingsError> {
+    let defaults = MemorySettings::default();
+    let max_allowed_memory_usage =
+        max_allowed_memory_usage.unwrap_or(defaults.max_allowed_memory_usage);
+    let preallocated_parsing_buffer_size = match preallocated_parsing_buffer_size {
+        Some(size) if size > max_allowed_memory_usage => {
+            return Err(SettingsError::PreallocatedBufferTooLarge {
+                preallocated_parsing_buffer_size: size,
+                max_allowed_memory_usage,
+            })
+        }
+        Some(size) => size,
+        None => defaults
+            .preallocated_parsing_buffer_size
+            .min(max_allowed_memory_usage),
+    };
+
+    Ok(MemorySettings {
+        max_allowed_memory_usage,
+        preallocated_parsing_buffer_size,
+    })
+}
+
+/// An entry of `element_content_handlers`, either a content handler calling into Python or
+/// a native rule.
+pub(crate) enum ElementHandler {
+    Python(Py<PyElementContentHandler>),
+    Native(NativeRule),
+}
+
+impl<'a> FromPyObject<'a> for ElementHandler {
+    fn extract(obj: &'a PyAny) -> PyResult<Self> {
+        match obj.extract::<PyRef<'_, PyRule>>() {
+            Ok(rule) => Ok(ElementHandler::Native(rule.rule.clone())),
+            Err(_) => Ok(ElementHandler::Python(obj.extract()?)),
+        }
+    }
+}
+
+/// Settings shared by all the rewriting entry points.
+///
+/// Content handlers and the encoding are validated once, while lol_html settings are built
+/// anew for every rewrite, as lol_html consumes them.
+pub(crate) struct RewriterSettings {
+    pub(crate) element_content_handlers: Vec<ElementHandler>,
+    pub(crate) document_content_handlers: Vec<Py<PyDocumentContentHandler>>,
+    pub(crate) encoding: AsciiCompatibleEncoding,
+    pub(crate) max_allowed_memory_usage: usize,
+    pub(crate) preallocated_parsing_buffer_size: usize,
+    pub(crate) strict: bool,
+    /// Called with a `ParsingAmbiguityError` for every ambiguity met in the non-strict mode.
+    pub(crate) on_parsing_ambiguity: Option<Arc<PyObject>>,
+}
+
+impl RewriterSettings {
+    /// Validates the settings, falling back to lol_html defaults for the omitted values.
+    pub(crate) fn new(
+        element_content_handlers: Vec<ElementHandler>,
+        document_content_handlers: Vec<Py<PyDocumentContentHandler>>,
+        encoding: &str,
+        max_allowed_memory_usage: Option<usize>,
+        preallocated_parsing_buffer_size: Option<usize>,
+        strict: bool,
+        on_parsing_ambiguity: Option<PyObject>,
+    ) -> PyResult<Self> {
+        let memory_settings =
+            memory_settings(max_allowed_memory_usage, preallocated_parsing_buffer_size)?;
+
+        // NOTE: ambiguities abort the rewriting in the strict mode, so there is nothing to report.
+        if strict && on_parsing_ambiguity.is_some() {
+            return Err(PyValueError::new_err(
+                "`on_parsing_ambiguity` can only be used with `strict=False`.",
+            ));
+        }
+
+        Ok(Self {
+            element_content_handlers,
+            document_content_handlers,
+            encoding: parse_encoding(encoding)?,
+            max_allowed_memory_usage: memory_settings.max_allowed_memory_usage,
+            preallocated_parsing_buffer_size: memory_settings.preallocated_parsing_buffer_size,
+            strict,
+            on_parsing_ambiguity: on_parsing_ambiguity.map(Arc::new),
+        })
+    }
+
+    /// Returns `true` if the rewriting calls into Python, i.e. there are Python content
+    /// handlers or an `on_parsing_ambiguity` callback.
+    ///
+    /// Otherwise the rewriting doesn't need the GIL and can run with it released.
+    pub(crate) fn calls_python(&self) -> bool {
+        self.element_content_handlers
+            .iter()
+            .any(|handler| matches!(handler, ElementHandler::Python(_)))
+            || !self.document_content_handlers.is_empty()
+            || self.on_parsing_ambiguity.is_some()
+    }
+
+    /// Builds lol_html settings with the native rules only, skipping the content handlers that
+    /// call into Python.
+    ///
+    /// Doesn't need the GIL, so can be used with it released.
+    pub(crate) fn build_native(&self) -> Settings<'static, 'static> {
+        Settings {
+            element_content_handlers: self
+                .element_content_handlers
+                .iter()
+                .filter_map(|handler| match handler {
+                    ElementHandler::Native(rule) => Some(rule.as_element_content_handlers()),
+                    ElementHandler::Python(_) => None,
+                })
+                .collect(),
+            ..self.build_base()
+        }
+    }
+
+    /// Builds lol_html settings with fresh content handlers.
+    pub(crate) fn build(&self, py: Python<'_>) -> Settings<'static, 'static> {
+        let tracker = self
+            .on_parsing_ambiguity
+            .as_ref()
+            .map(|_| ambiguity::end_tag_tracker());
+        // NOTE: ambiguities are tracked before the user's handlers see the element.
+        let reporting_handlers = self
+            .on_parsing_ambiguity
+            .clone()
+            .zip(tracker.clone())
+            .map(|(callback, tracker)| ambiguity::reporting_handlers(tracker, callback));
+
+        Settings {
+            element_content_handlers: reporting_handlers
+                .into_iter()
+                .chain(
+                    self.element_content_handlers
+                        .iter()
+                        .map(|handler| match handler {
+                            ElementHandler::Python(handler) => {
+                                PyElementContentHandler::as_element_content_handlers(
+                                    handler.as_ref(py),
+                                    tracker.clone(),
+                                )
+                            }
+                            ElementHandler::Native(rule) => rule.as_element_content_handlers(),
+                        }),
+                )
+                .collect(),
+            document_content_handlers: self
+                .document_content_handlers
+                .iter()
+                .map(|handler| {
+                    PyDocumentContentHandler::as_document_content_handlers(handler.as_ref(py))
+                })
+                .collect(),
+            ..self.build_base()
+        }
+    }
+
+    /// Builds lol_html settings without content handlers.
+    fn build_base(&self) -> Settings<'static, 'static> {
+        Settings {
+            encoding: self.encoding,
+            memory_settings: MemorySettings {
+                max_allowed_memory_usage: self.max_allowed_memory_usage,
+                preallocated_parsing_buffer_size: self.preallocated_parsing_buffer_size,
+            },
+            strict: self.strict,
+            ..Settings::default()
+        }
+    }
+}
+
+/// Parses a CSS selector, naming the selector and the kind of error on failure.
+pub(crate) fn parse_selector(selector: &str) -> PyResult<Selector> {
+    Ok(compile_selector(selector)?)
+}
+
+/// The default of `max_text_node_size`, 1 MiB.
+const DEFAULT_MAX_TEXT_NODE_SIZE: usize = 1024 * 1024;
+
+/// How text is passed to `text` handlers.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum TextMode {
+    /// Text chunks are passed as lol_html produces them.
+    Chunk,
+    /// Chunks are buffered, so that the handler is called once per text node.
+    Node,
+}
+
+impl TextMode {
+    fn parse(text_mode: &str) -> PyResult<Self> {
+        match text_mode {
+            "chunk" => Ok(TextMode::Chunk),
+            "node" => Ok(TextMode::Node),
+            _ => Err(PyValueError::new_err(format!(
+                "Unknown text mode `{}`, expected `chunk` or `node`.",
+                text_mode
+            ))),
+        }
+    }
+}
+
+/// Settings of `text` handlers.
+#[derive(Clone)]
+pub(crate) struct TextSettings {
+    /// Types of the text passed to the handler, all of them if not given.
+    pub(crate) text_types: Option<Vec<PyTextType>>,
+    pub(crate) text_mode: TextMode,
+    /// The maximum size of a text node buffered in the node mode, in bytes.
+    pub(crate) max_text_node_size: usize,
+}
+
+impl TextSettings {
+    fn new(
+        text_types: Option<Vec<PyTextType>>,
+        text_mode: &str,
+        max_text_node_size: usize,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            text_types,
+            text_mode: TextMode::parse(text_mode)?,
+            max_text_node_size,
+        })
+    }
+
+    /// Returns `true` if the type of the text `chunk` is one of `text_types`, if those are given.
+    fn has_text_type(&self, chunk: &TextChunk) -> bool {
+        match &self.text_types {
+            Some(text_types) => text_types.contains(&chunk.text_type().into()),
+            None => true,
+        }
+    }
+}
+
+/// Builds a lol_html text handler calling the Python `handler` according to `settings`.
+///
+/// In the node mode, all but the last chunk of a text node are removed from the output and
+/// buffered. The handler is then called with the last chunk, which exposes the text of the whole
+/// node, and the buffered text is re-emitted in place of the chunk, unless the handler has
+/// removed or replaced it. Same as in the chunk mode, a `str` returned by the handler replaces
+/// the node as text, so the text it was given has to be returned as `Markup` to keep its
+/// character references from being escaped again.
+fn text_handler(
+    handler: Arc<PyObject>,
+    settings: TextSettings,
+) -> impl FnMut(&mut TextChunk) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut node_text = String::new();
+
+    move |chunk: &mut TextChunk| {
+        if !settings.has_text_type(chunk) {
+            return Ok(());
+        }
+
+        if settings.text_mode == TextMode::Chunk {
+            let chunk: &'static mut TextChunk = unsafe { std::mem::transmute(chunk) };
+            return Python::with_gil(|py| {
+                let _result = call_with_unit(py, &handler, PyTextChunk::new(chunk))?;
+                Ok(())
+            });
+        }
+
+        if node_text.len() + chunk.as_str().len() > settings.max_text_node_size {
+            node_text.clear();
+            return Err(Box::new(MemoryLimitExceededError::new_err(format!(
+                "Text node exceeds `max_text_node_size` ({} bytes).",
+                settings.max_text_node_size
+            ))));
+        }
+
+        node_text.push_str(chunk.as_str());
+
+        if !chunk.last_in_text_node() {
+            chunk.remove();
+            return Ok(());
+        }
+
+        let text = std::mem::take(&mut node_text);
+        let is_buffered = text.len() > chunk.as_str().len();
+        // NOTE: the chunk is lent to the handler and used again once the wrapper expires.
+        let unit: &'static mut TextChunk =
+            unsafe { std::mem::transmute(&mut *(chunk as *mut TextChunk)) };
+
+        Python::with_gil(|py| {
+            let _result = call_with_unit(py, &handler, PyTextChunk::with_node_text(unit, &text))?;
+            Ok::<_, PyErr>(())
+        })?;
+
+        // NOTE: the text is passed to the handler as it is in the source, so it is re-emitted
+        // as HTML to keep character references intact.
+        if is_buffered && !chunk.removed() {
+            chunk.replace(&text, ContentType::Html);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the callable handling the units of `kind`.
+///
+/// A callable given explicitly takes precedence over a method of the `handler` object, which in
+/// turn takes precedence over a method defined by a subclass of the content handler class.
+fn find_handler(
+    this: &PyAny,
+    explicit: &Option<Arc<PyObject>>,
+    handler: &Option<PyObject>,
+    kind: &str,
+) -> Option<Arc<PyObject>> {
+    if explicit.is_some() {
+        return explicit.clone();
+    }
+
+    let py = this.py();
+
+    handler
+        .iter()
+        .map(|handler| handler.as_ref(py))
+        .chain(std::iter::once(this))
+        .filter_map(|obj| obj.getattr(kind).ok())
+        .find(|method| method.is_callable())
+        .map(|method| Arc::new(method.into()))
+}
+
+/// Content handlers for the elements matched by the `selector`.
+///
+/// Handlers can be given as callables, as methods of the `handler` object, or as methods of
+/// a subclass.
+#[pyclass(subclass, name = "ElementContentHandler")]
 pub(crate) struct PyElementContentHandler {
     pub(crate) selector: String,
+    /// Compiled `selector`, cached so that it isn't re-parsed on every rewrite.
+    pub(crate) compiled_selector: Selector,
     pub(crate) element: Option<Arc<PyObject>>,
     pub(crate) comments: Option<Arc<PyObject>>,
     pub(crate) text: Option<Arc<PyObject>>,
+    /// Called with the end tag and the tag name of every matched element.
+    pub(crate) end_tag: Option<Arc<PyObject>>,
+    /// An object implementing some of the handlers as methods.
+    pub(crate) handler: Option<PyObject>,
+    pub(crate) text_settings: TextSettings,
 }
 
 #[pymethods]
 impl PyElementContentHandler {
     #[new]
-    #[args(html, "*", element, comments, text)]
+    #[allow(clippy::too_many_arguments)]
+    #[args(
+        selector,
+        handler = "None",
+        "*",
+        element = "None",
+        comments = "None",
+        text = "None",
+        end_tag = "None",
+        text_types = "None",
+        text_mode = "\"chunk\"",
+        max_text_node_size = "DEFAULT_MAX_TEXT_NODE_SIZE"
+    )]
     fn __new__(
         selector: &str,
+        handler: Option<PyObject>,
         element: Option<PyObject>,
         comments: Option<PyObject>,
         text: Option<PyObject>,
-    ) -> Self {
-        Self {
+        end_tag: Option<PyObject>,
+        text_types: Option<Vec<PyTextType>>,
+        text_mode: &str,
+        max_text_node_size: usize,
+    ) -> PyResult<Self> {
+        Ok(Self {
             selector: selector.to_owned(),
+            compiled_selector: parse_selector(selector)?,
             element: element.map(Arc::new),
             comments: comments.map(Arc::new),
             text: text.map(Arc::new),
-        }
+            end_tag: end_tag.map(Arc::new),
+            handler,
+            text_settings: TextSettings::new(text_types, text_mode, max_text_node_size)?,
+        })
+    }
+
+    /// The CSS selector of the handler.
+    #[getter]
+    fn selector(&self) -> &str {
+        &self.selector
     }
 }
 
 impl PyElementContentHandler {
-    pub fn as_element_content_handlers<'h>(
-        &self,
+    /// Builds lol_html content handlers, letting the ambiguity `tracker` see the end tags of the
+    /// elements, if it is given.
+    pub(crate) fn as_element_content_handlers<'h>(
+        slf: &PyCell<Self>,
+        tracker: Option<EndTagTracker>,
     ) -> (Cow<'h, Selector>, ElementContentHandlers<'h>) {
+        let this = slf.borrow();
+        let find = |explicit, kind| find_handler(slf, explicit, &this.handler, kind);
         let mut handlers = ElementContentHandlers::default();
 
-        if let Some(handler) = self.element.clone() {
-            handlers = handlers.element(move |elem: &mut _| {
-                let elem: &'static mut Element = unsafe { std::mem::transmute(elem) };
-                Python::with_gil(|py| {
-                    let _result = handler.call(py, (PyElement::new(elem),), None)?;
-                    Ok(())
-                })
+        let element = find(&this.element, "element");
+        let end_tag = find(&this.end_tag, "end_tag");
+
+        if element.is_some() || end_tag.is_some() {
+            handlers = handlers.element(move |elem: &mut Element| {
+                // NOTE: the end tag handler is attached first, so that `Element.on_end_tag`
+                // called by the element handler takes precedence over it.
+                if let Some(handler) = end_tag.clone() {
+                    let tag_name = elem.tag_name();
+
+                    // NOTE: elements without an end tag (e.g. void elements) are skipped.
+                    set_end_tag_handler(
+                        elem,
+                        EndTagTracker::wrap(tracker.clone(), move |end: &mut _| {
+                            let end: &'static mut EndTag = unsafe { std::mem::transmute(end) };
+                            Python::with_gil(|py| {
+                                let _result = call_with_unit_and_arg(
+                                    py,
+                                    &handler,
+                                    PyEndTag::new(end),
+                                    tag_name.as_str(),
+                                )?;
+                                Ok(())
+                            })
+                        }),
+                    );
+                }
+
+                if let Some(handler) = &element {
+                    let elem: &'static mut Element = unsafe { std::mem::transmute(elem) };
+                    Python::with_gil(|py| {
+                        let _result =
+                            call_with_unit(py, handler, PyElement::new(elem, tracker.clone()))?;
+                        Ok::<_, PyErr>(())
+                    })?;
+                }
+
+                Ok(())
             })
         }
 
-        if let Some(handler) = self.comments.clone() {
+        if let Some(handler) = find(&this.comments, "comments") {
             handlers = handlers.comments(move |comment: &mut _| {
                 let comment: &'static mut Comment = unsafe { std::mem::transmute(comment) };
                 Python::with_gil(|py| {
-                    let _result = handler.call(py, (PyComment::new(comment),), None)?;
+                    let _result = call_with_unit(py, &handler, PyComment::new(comment))?;
                     Ok(())
                 })
             })
         }
 
-        if let Some(handler) = self.text.clone() {
-            handlers = handlers.text(move |text: &mut _| {
-                let elem: &'static mut TextChunk = unsafe { std::mem::transmute(text) };
-                Python::with_gil(|py| {
-                    let _result = handler.call(py, (PyTextChunk::new(elem),), None)?;
-                    Ok(())
-                })
-            })
+        if let Some(handler) = find(&this.text, "text") {
+            handlers = handlers.text(text_handler(handler, this.text_settings.clone()))
         }
 
-        (Cow::Owned(self.selector.parse().unwrap()), handlers)
+        (Cow::Owned(this.compiled_selector.clone()), handlers)
     }
 }
 
-#[pyclass(name = "DocumentContentHandler")]
+/// Content handlers for the whole document.
+///
+/// Handlers can be given as callables, as methods of the `handler` object, or as methods of
+/// a subclass.
+#[pyclass(subclass, name = "DocumentContentHandler")]
 pub(crate) struct PyDocumentContentHandler {
     pub(crate) doctype: Option<Arc<PyObject>>,
     pub(crate) comments: Option<Arc<PyObject>>,
     pub(crate) text: Option<Arc<PyObject>>,
     pub(crate) end: Option<Arc<PyObject>>,
+    /// An object implementing some of the handlers as methods.
+    pub(crate) handler: Option<PyObject>,
+    pub(crate) text_settings: TextSettings,
 }
 
 #[pymethods]
 impl PyDocumentContentHandler {
     #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[args(
+        doctype,
+        comments,
+        text,
+        end,
+        "*",
+        handler,
+        text_types,
+        text_mode = "\"chunk\"",
+        max_text_node_size = "DEFAULT_MAX_TEXT_NODE_SIZE"
+    )]
     fn __new__(
         doctype: Option<PyObject>,
         comments: Option<PyObject>,
         text: Option<PyObject>,
         end: Option<PyObject>,
-    ) -> Self {
-        Self {
+        handler: Option<PyObject>,
+        text_types: Option<Vec<PyTextType>>,
+        text_mode: &str,
+        max_text_node_size: usize,
+    ) -> PyResult<Self> {
+        Ok(Self {
             doctype: doctype.map(Arc::new),
             comments: comments.map(Arc::new),
             text: text.map(Arc::new),
             end: end.map(Arc::new),
-        }
+            handler,
+            text_settings: TextSettings::new(text_types, text_mode, max_text_node_size)?,
+        })
     }
 }
 
 impl PyDocumentContentHandler {
-    pub fn as_document_content_handlers<'h>(&self) -> DocumentContentHandlers<'h> {
+    pub fn as_document_content_handlers<'h>(slf: &PyCell<Self>) -> DocumentContentHandlers<'h> {
+        let this = slf.borrow();
+        let find = |explicit, kind| find_handler(slf, explicit, &this.handler, kind);
         let mut handlers = DocumentContentHandlers::default();
 
-        if let Some(handler) = self.doctype.clone() {
+        if let Some(handler) = find(&this.doctype, "doctype") {
             handlers = handlers.doctype(move |doctype: &mut _| {
-                let doctype: &'static mut Element = unsafe { std::mem::transmute(doctype) };
+                let doctype: &'static mut Doctype = unsafe { std::mem::transmute(doctype) };
                 Python::with_gil(|py| {
-                    let _result = handler.call1(py, (PyElement::new(doctype),))?;
+                    let _result = call_with_unit(py, &handler, PyDoctype::new(doctype))?;
                     Ok(())
                 })
             })
         }
 
-        if let Some(handler) = self.comments.clone() {
+        if let Some(handler) = find(&this.comments, "comments") {
             handlers = handlers.comments(move |comments: &mut _| {
                 let comments: &'static mut Comment = unsafe { std::mem::transmute(comments) };
                 Python::with_gil(|py| {
-                    let _result = handler.call1(py, (PyComment::new(comments),))?;
+                    let _result = call_with_unit(py, &handler, PyComment::new(comments))?;
                     Ok(())
                 })
             })
         }
 
-        if let Some(handler) = self.text.clone() {
-            handlers = handlers.text(move |text: &mut _| {
-                let text: &'static mut TextChunk = unsafe { std::mem::transmute(text) };
-                Python::with_gil(|py| {
-                    let _result = handler.call1(py, (PyTextChunk::new(text),))?;
-                    Ok(())
-                })
-            })
+        if let Some(handler) = find(&this.text, "text") {
+            handlers = handlers.text(text_handler(handler, this.text_settings.clone()))
         }
 
-        if let Some(handler) = self.end.clone() {
+        if let Some(handler) = find(&this.end, "end") {
             handlers = handlers.end(move |end: &mut _| {
                 let end: &'static mut DocumentEnd = unsafe { std::mem::transmute(end) };
                 Python::with_gil(|py| {
-                    let _result = handler.call1(py, (PyDocumentEnd::new(end),))?;
+                    let _result = call_with_unit(py, &handler, PyDocumentEnd::new(end))?;
                     Ok(())
                 })
             })
@@ -0,0 +1,157 @@
+from collections.abc import ItemsView, KeysView, MutableMapping, ValuesView
+
+from lolhtml import (
+    AttributeNameError,
+    ElementContentHandler,
+    RewritableUnitExpiredError,
+    rewrite_str,
+)
+import pytest
+
+
+def rewrite_attrs(html: str, handler) -> str:
+    return rewrite_str(
+        html, element_content_handlers=[ElementContentHandler("a", element=handler)]
+    )
+
+
+def test_mapping_access():
+    def handler(el):
+        attrs = el.attrs
+
+        assert isinstance(attrs, MutableMapping)
+        assert len(attrs) == 3
+        assert list(attrs) == ["href", "id", "rel"]
+        assert list(attrs.items()) == [("href", "/foo"), ("id", "x"), ("rel", "")]
+        assert "href" in attrs and "HREF" in attrs and "title" not in attrs
+        assert attrs["href"] == "/foo"
+        assert attrs.get("title") is None
+        assert attrs.get("title", "none") == "none"
+        assert repr(attrs) == "Attributes({'href': '/foo', 'id': 'x', 'rel': ''})"
+
+        with pytest.raises(KeyError):
+            attrs["title"]
+
+    rewrite_attrs(r'<a href="/foo" id=x rel>', handler)
+
+
+def test_mapping_mutation():
+    def handler(el):
+        attrs = el.attrs
+
+        attrs["href"] = "/bar"
+        del attrs["id"]
+        attrs.update({"title": "Bar"}, target="_blank")
+        attrs.update([("rel", "noopener")])
+
+        assert attrs.pop("data-x") == "1"
+        assert attrs.pop("data-y", None) is None
+
+        with pytest.raises(KeyError):
+            del attrs["id"]
+
+        with pytest.raises(KeyError):
+            attrs.pop("id")
+
+        with pytest.raises(AttributeNameError):
+            attrs["invalid name"] = ""
+
+    result = rewrite_attrs(r'<a href="/foo" id="x" data-x="1">', handler)
+
+    assert result == r'<a href="/bar" title="Bar" target="_blank" rel="noopener">'
+
+
+def test_view_is_live():
+    def handler(el):
+        attrs = el.attrs
+
+        el.set_attribute("title", "Foo")
+        assert attrs["title"] == "Foo"
+
+        for name in attrs:
+            if name.startswith("on"):
+                del attrs[name]
+
+    result = rewrite_attrs(r'<a onclick="x()" href="/" onmouseover="y()">', handler)
+
+    assert result == r'<a href="/" title="Foo">'
+
+
+def test_clear():
+    result = rewrite_attrs(r'<a href="/" id="x">', lambda el: el.attrs.clear())
+
+    assert result == r"<a>"
+
+
+def test_view_expires_with_element():
+    views = []
+
+    rewrite_attrs(r'<a href="/">', lambda el: views.append(el.attrs))
+
+    with pytest.raises(RewritableUnitExpiredError):
+        views[0]["href"]
+
+
+def test_views_are_live():
+    def handler(el):
+        keys, values, items = el.attrs.keys(), el.attrs.values(), el.attrs.items()
+
+        assert isinstance(keys, KeysView) and isinstance(items, ItemsView)
+        assert isinstance(values, ValuesView)
+
+        el.set_attribute("title", "Foo")
+        el.remove_attribute("id")
+
+        assert list(keys) == ["href", "title"]
+        assert list(values) == ["/", "Foo"]
+        assert list(items) == [("href", "/"), ("title", "Foo")]
+        assert "title" in keys and ("title", "Foo") in items and "Foo" in values
+        assert len(items) == 2
+
+    rewrite_attrs(r'<a href="/" id="x">', handler)
+
+
+def test_setdefault():
+    def handler(el):
+        assert el.attrs.setdefault("href", "/bar") == "/"
+        assert el.attrs.setdefault("target", "_blank") == "_blank"
+        assert el.attrs.setdefault("download") == ""
+
+    result = rewrite_attrs(r'<a href="/">', handler)
+
+    assert result == r'<a href="/" target="_blank" download="">'
+
+
+def test_popitem():
+    def handler(el):
+        assert el.attrs.popitem() == ("id", "x")
+        assert el.attrs.popitem() == ("href", "/")
+
+        with pytest.raises(KeyError):
+            el.attrs.popitem()
+
+    assert rewrite_attrs(r'<a href="/" id="x">', handler) == r"<a>"
+
+
+def test_equality():
+    def handler(el):
+        assert el.attrs == {"id": "x", "href": "/"}
+        assert el.attrs != {"href": "/"}
+        assert el.attrs != [("href", "/"), ("id", "x")]
+        assert el.attrs == el.attrs
+        assert {"href": "/", "id": "x"} == el.attrs
+
+        with pytest.raises(TypeError):
+            el.attrs < {}
+
+    rewrite_attrs(r'<a href="/" id="x">', handler)
+
+
+def test_delete_while_iterating_keys():
+    def handler(el):
+        for name in el.attrs.keys():
+            del el.attrs[name]
+
+        assert len(el.attrs) == 0
+
+    assert rewrite_attrs(r'<a href="/" id="x">', handler) == r"<a>"
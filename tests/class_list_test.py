@@ -0,0 +1,81 @@
+from lolhtml import ElementContentHandler, rewrite_str
+import pytest
+
+
+def rewrite_classes(html: str, handler) -> str:
+    return rewrite_str(
+        html, element_content_handlers=[ElementContentHandler("div", element=handler)]
+    )
+
+
+def test_read():
+    def handler(el):
+        classes = el.class_list
+
+        assert list(classes) == ["foo", "bar", "baz"]
+        assert len(classes) == 3
+        assert "bar" in classes
+        assert classes.contains("baz")
+        assert not classes.contains("qux")
+        assert repr(classes) == 'ClassList("foo bar baz")'
+
+    result = rewrite_classes('<div class=" foo\tbar\n\nfoo baz ">', handler)
+
+    assert result == '<div class=" foo\tbar\n\nfoo baz ">'
+
+
+def test_add_and_remove_normalize_whitespace():
+    def handler(el):
+        el.class_list.add("qux", "foo")
+        el.class_list.remove("bar", "missing")
+
+    result = rewrite_classes('<div class="  foo\tbar  foo ">', handler)
+
+    assert result == '<div class="foo qux">'
+
+
+def test_toggle():
+    def handler(el):
+        classes = el.class_list
+
+        assert classes.toggle("foo") is False
+        assert classes.toggle("bar") is True
+        assert classes.toggle("bar", True) is True
+        assert classes.toggle("baz", False) is False
+
+    result = rewrite_classes('<div class="foo">', handler)
+
+    assert result == '<div class="bar">'
+
+
+def test_replace():
+    def handler(el):
+        classes = el.class_list
+
+        assert classes.replace("missing", "x") is False
+        assert classes.replace("c", "a") is True
+        assert classes.replace("b", "d") is True
+
+    result = rewrite_classes('<div class="a b c">', handler)
+
+    assert result == '<div class="a d">'
+
+
+def test_no_class_attribute():
+    def handler(el):
+        el.class_list.remove("foo")
+        assert len(el.class_list) == 0
+
+    assert rewrite_classes("<div>", handler) == "<div>"
+    assert rewrite_classes('<div class="foo">', handler) == '<div class="">'
+
+
+def test_invalid_class_names():
+    def handler(el):
+        with pytest.raises(ValueError):
+            el.class_list.add("")
+
+        with pytest.raises(ValueError):
+            el.class_list.toggle("foo bar")
+
+    rewrite_classes("<div>", handler)
@@ -0,0 +1,177 @@
+import os
+from pathlib import Path
+import subprocess
+
+import pytest
+
+BIN = Path(
+    os.environ.get(
+        "LOLHTML_BIN", Path(__file__).parent.parent / "target" / "debug" / "lolhtml"
+    )
+)
+
+requires_bin = pytest.mark.skipif(
+    not BIN.exists(), reason="the binary isn't built, run `cargo build --features cli`"
+)
+
+RULES = """
+[[rules]]
+selector = "a"
+action = "set_attribute"
+name = "rel"
+value = "noopener"
+
+[[rules]]
+selector = "script"
+action = "remove_element"
+"""
+
+
+def run(tmp_path, *args, input=None):
+    rules = tmp_path / "rules.toml"
+    rules.write_text(RULES)
+
+    return subprocess.run(
+        [BIN, "--rules", rules, *args],
+        input=input,
+        capture_output=True,
+        cwd=tmp_path,
+    )
+
+
+@requires_bin
+def test_stdin(tmp_path):
+    result = run(tmp_path, input=b"<a>x</a><script>y</script>")
+
+    assert result.returncode == 0
+    assert result.stdout == b'<a rel="noopener">x</a>'
+
+
+@requires_bin
+def test_file(tmp_path):
+    (tmp_path / "index.html").write_text("<a>x</a>")
+    result = run(tmp_path, "index.html")
+
+    assert result.returncode == 0
+    assert result.stdout == b'<a rel="noopener">x</a>'
+    assert (tmp_path / "index.html").read_text() == "<a>x</a>"
+
+
+@requires_bin
+def test_in_place(tmp_path):
+    (tmp_path / "index.html").write_text("<a>x</a>")
+    (tmp_path / "index.html").chmod(0o640)
+    result = run(tmp_path, "--in-place", "index.html")
+
+    assert result.returncode == 0
+    assert result.stdout == b""
+    assert (tmp_path / "index.html").read_text() == '<a rel="noopener">x</a>'
+    assert (tmp_path / "index.html").stat().st_mode & 0o777 == 0o640
+
+
+@requires_bin
+def test_in_place_failure_keeps_file(tmp_path):
+    (tmp_path / "index.html").write_text("<select><xmp>")
+    result = run(tmp_path, "--in-place", "index.html")
+
+    assert result.returncode == 1
+    assert b"index.html" in result.stderr
+    assert (tmp_path / "index.html").read_text() == "<select><xmp>"
+    assert sorted(p.name for p in tmp_path.iterdir()) == ["index.html", "rules.toml"]
+
+
+@requires_bin
+def test_recursive(tmp_path):
+    site = tmp_path / "site"
+    (site / "sub").mkdir(parents=True)
+    (site / "drafts").mkdir()
+    (site / "index.html").write_text("<a>1</a>")
+    (site / "sub" / "page.htm").write_text("<a>2</a>")
+    (site / "drafts" / "draft.html").write_text("<a>3</a>")
+    (site / "notes.txt").write_text("<a>4</a>")
+
+    result = run(tmp_path, "-r", "--exclude", "drafts/**", "-o", "out", "site")
+
+    assert result.returncode == 0
+    assert sorted(
+        str(p.relative_to(tmp_path / "out"))
+        for p in (tmp_path / "out").rglob("*")
+        if p.is_file()
+    ) == ["index.html", os.path.join("sub", "page.htm")]
+    assert (tmp_path / "out" / "sub" / "page.htm").read_text() == (
+        '<a rel="noopener">2</a>'
+    )
+
+    result = run(tmp_path, "-r", "-i", "--include", "*.txt", "site")
+
+    assert result.returncode == 0
+    assert (site / "notes.txt").read_text() == '<a rel="noopener">4</a>'
+    assert (site / "index.html").read_text() == "<a>1</a>"
+
+
+@requires_bin
+def test_same_output_path(tmp_path):
+    for dir in ["a", "b"]:
+        (tmp_path / dir).mkdir()
+        (tmp_path / dir / "index.html").write_text("<a>x</a>")
+
+    result = run(tmp_path, "-o", "out", "a/index.html", "b/index.html")
+
+    assert result.returncode == 1
+    assert b"a/index.html and b/index.html" in result.stderr
+    assert not (tmp_path / "out").exists()
+
+    result = run(tmp_path, "-i", "a/index.html", "a/index.html")
+
+    assert result.returncode == 1
+    assert (tmp_path / "a" / "index.html").read_text() == "<a>x</a>"
+
+
+@requires_bin
+def test_directory_requires_recursive(tmp_path):
+    (tmp_path / "site").mkdir()
+    result = run(tmp_path, "-i", "site")
+
+    assert result.returncode == 1
+    assert b"--recursive" in result.stderr
+
+
+@requires_bin
+def test_settings(tmp_path):
+    result = run(
+        tmp_path, "--encoding", "windows-1251", input="<a>Ы</a>".encode("windows-1251")
+    )
+
+    assert result.stdout == '<a rel="noopener">Ы</a>'.encode("windows-1251")
+
+    result = run(tmp_path, "--encoding", "utf-16", input=b"")
+
+    assert result.returncode == 1
+    assert b"ASCII-compatible" in result.stderr
+
+    result = run(tmp_path, "--encoding", "utf-42", input=b"")
+
+    assert result.returncode == 1
+    assert b"Unknown encoding `utf-42`" in result.stderr
+
+    result = run(tmp_path, "--max-memory", "64", input=b'<a href="' + b"x" * 1024)
+
+    assert result.returncode == 1
+    assert b"memory limit" in result.stderr
+
+    result = run(tmp_path, input=b"<select><xmp><script>")
+
+    assert result.returncode == 1
+    assert b"ambiguous" in result.stderr
+    assert run(tmp_path, "--no-strict", input=b"<select><xmp>").returncode == 0
+
+
+@requires_bin
+def test_invalid_rules(tmp_path):
+    rules = tmp_path / "bad.json"
+    rules.write_text('{"rules": [{"selector": "a", "action": "explode"}]}')
+
+    result = subprocess.run([BIN, "--rules", rules], input=b"", capture_output=True)
+
+    assert result.returncode == 1
+    assert b"rules[0].action" in result.stderr
@@ -1,6 +1,13 @@
 #!/usr/bin/env python3
 
-from lolhtml import ContentType, rewrite_str, ElementContentHandler, TagNameError
+from lolhtml import (
+    CommentTextError,
+    ContentType,
+    rewrite_bytes,
+    rewrite_str,
+    ElementContentHandler,
+    TagNameError,
+)
 import pytest
 
 ############################
@@ -49,3 +56,43 @@ def test_replace():
     )
 
     assert result == r"<div>Qux</div>"
+
+
+def test_set_text():
+    def handler(comment):
+        comment.before("<!-- 42 -->", ContentType.Html)
+        comment.set_text(" redacted ")
+
+    result = rewrite_str(
+        r"<div><!-- secret --></div>",
+        element_content_handlers=[ElementContentHandler("div", comments=handler)],
+    )
+
+    assert result == r"<div><!-- 42 --><!-- redacted --></div>"
+
+
+def test_set_text_with_closing_sequence():
+    def handler(comment):
+        with pytest.raises(CommentTextError):
+            comment.set_text(" foo --> bar ")
+
+    result = rewrite_str(
+        r"<div><!-- foo --></div>",
+        element_content_handlers=[ElementContentHandler("div", comments=handler)],
+    )
+
+    assert result == r"<div><!-- foo --></div>"
+
+
+def test_set_text_with_unencodable_character():
+    def handler(comment):
+        comment.set_text("Привет")
+
+    with pytest.raises(CommentTextError):
+        rewrite_bytes(
+            b"<div><!-- foo --></div>",
+            encoding="windows-1252",
+            element_content_handlers=[
+                ElementContentHandler("div", comments=handler)
+            ],
+        )
@@ -0,0 +1,163 @@
+from lolhtml import (
+    ContentType,
+    Doctype,
+    DocumentContentHandler,
+    ElementContentHandler,
+    HtmlRewriter,
+    rewrite_str,
+)
+import pytest
+
+
+def rewrite_doctype(html: str, handler) -> str:
+    handler_called = False
+
+    def check_if_called(doctype):
+        nonlocal handler_called
+        handler_called = True
+        assert isinstance(doctype, Doctype)
+        handler(doctype)
+
+    result = rewrite_str(
+        html,
+        document_content_handlers=[DocumentContentHandler(doctype=check_if_called)],
+    )
+
+    assert handler_called, "Handler not called."
+
+    return result
+
+
+def test_info():
+    def handler(doctype):
+        assert doctype.name() == "html"
+        assert doctype.public_id() == "-//W3C//DTD XHTML 1.0 Transitional//EN"
+        assert doctype.system_id() == "DTD/xhtml1-transitional.dtd"
+
+    html = r'<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "DTD/xhtml1-transitional.dtd">'
+
+    assert rewrite_doctype(html, handler) == html
+
+
+def test_empty_doctype():
+    def handler(doctype):
+        assert doctype.name() is None
+        assert doctype.public_id() is None
+        assert doctype.system_id() is None
+
+    rewrite_doctype(r"<!doctype>", handler)
+
+
+def test_html5_doctype():
+    def handler(doctype):
+        assert doctype.name() == "html"
+        assert doctype.public_id() is None
+        assert doctype.system_id() is None
+
+    rewrite_doctype(r"<!DOCTYPE html><html></html>", handler)
+
+
+def test_remove():
+    html = r"<!DOCTYPE html PUBLIC '-//W3C//DTD HTML 4.01//EN'><html><!doctype html></html>"
+
+    assert rewrite_doctype(html, lambda doctype: doctype.remove()) == r"<html></html>"
+
+
+def test_remove_by_returning_false():
+    def handler(doctype):
+        return False if doctype.public_id() else None
+
+    html = r"<!doctype html public 'legacy'>text<!DOCTYPE html>"
+
+    result = rewrite_str(
+        html, document_content_handlers=[DocumentContentHandler(doctype=handler)]
+    )
+
+    assert result == r"text<!DOCTYPE html>"
+
+
+def test_remove_streaming():
+    chunks = []
+    rewriter = HtmlRewriter(
+        chunks.append,
+        document_content_handlers=[DocumentContentHandler(doctype=lambda d: d.remove())],
+    )
+
+    for chunk in ["<!DOC", "TYPE html><p>", "hi</p><!doctype html>"]:
+        rewriter.write(chunk)
+
+    rewriter.end()
+
+    assert b"".join(chunks) == b"<p>hi</p>"
+
+
+def test_remove_in_removed_element():
+    # NOTE: doctypes inside removed elements aren't written at all.
+    html = r"<div><!doctype a></div><!-- keep --><!doctype b><!doctype c>"
+
+    result = rewrite_str(
+        html,
+        element_content_handlers=[ElementContentHandler("div", element=lambda el: el.remove())],
+        document_content_handlers=[
+            DocumentContentHandler(doctype=lambda d: d.remove() if d.name() != "b" else None)
+        ],
+    )
+
+    assert result == r"<!-- keep --><!doctype b>"
+
+
+def test_remove_keeps_inserted_doctypes():
+    html = r"<div><!doctype html></div><p>hi</p>"
+
+    result = rewrite_str(
+        html,
+        element_content_handlers=[
+            ElementContentHandler("div", element=lambda el: el.remove()),
+            ElementContentHandler(
+                "p", element=lambda el: el.before("<!DOCTYPE keep>", ContentType.Html)
+            ),
+        ],
+        document_content_handlers=[DocumentContentHandler(doctype=lambda d: d.remove())],
+    )
+
+    assert result == r"<!DOCTYPE keep><p>hi</p>"
+
+
+def test_removed():
+    removed = []
+
+    def handler(doctype):
+        removed.append(doctype.removed())
+        doctype.remove()
+        removed.append(doctype.removed())
+
+    assert rewrite_doctype(r"<!doctype html>", handler) == ""
+    assert removed == [False, True]
+
+
+def test_removed_doctype_is_not_written_again():
+    seen = []
+
+    def handler(doctype):
+        seen.append(doctype.name())
+        doctype.remove()
+
+    result = rewrite_str(
+        r"<!doctype a><!doctype b>",
+        document_content_handlers=[
+            DocumentContentHandler(doctype=handler),
+            DocumentContentHandler(doctype=lambda doctype: None),
+        ],
+    )
+
+    assert result == ""
+    assert seen == ["a", "b"]
+
+
+def test_remove_after_expiry():
+    doctypes = []
+
+    rewrite_doctype(r"<!doctype html>", doctypes.append)
+
+    with pytest.raises(Exception, match="content handler"):
+        doctypes[0].remove()
@@ -213,3 +213,118 @@ def test_on_end_tag():
 
     # print(result)
     assert result == r"<span>Short</SPAN><span><b>13</b> characters!</span>"
+
+
+def test_end_tag_handler():
+    def end_tag_handler(end, tag_name):
+        end.before(f"<!-- end of {tag_name} -->", ContentType.Html)
+
+    result = rewrite_str(
+        r"<body><div><br><p>Hello</p></div></body>",
+        element_content_handlers=[
+            ElementContentHandler("body, div, br", end_tag=end_tag_handler)
+        ],
+    )
+
+    assert (
+        result
+        == r"<body><div><br><p>Hello</p><!-- end of div --></div><!-- end of body --></body>"
+    )
+
+
+def test_end_tag_handler_gets_matched_tag_name():
+    names = []
+
+    def element_handler(el):
+        el.set_tag_name("section")
+
+    def end_tag_handler(end, tag_name):
+        names.append((end.name(), tag_name))
+
+    result = rewrite_str(
+        r"<div></div>",
+        element_content_handlers=[
+            ElementContentHandler(
+                "div", element=element_handler, end_tag=end_tag_handler
+            )
+        ],
+    )
+
+    assert result == r"<section></section>"
+    assert names == [("section", "div")]
+
+
+def test_on_end_tag_overrides_end_tag_handler():
+    def element_handler(el):
+        el.on_end_tag(lambda end: end.after("!", ContentType.Text))
+
+    result = rewrite_str(
+        r"<div></div>",
+        element_content_handlers=[
+            ElementContentHandler(
+                "div",
+                element=element_handler,
+                end_tag=lambda end, tag_name: end.after("?", ContentType.Text),
+            )
+        ],
+    )
+
+    assert result == r"<div></div>!"
+
+
+def test_end_tag_set_name():
+    def end_tag_handler(end, tag_name):
+        if tag_name == "b":
+            end.set_name("strong")
+        else:
+            end.set_name(b"em")
+
+    result = rewrite_str(
+        r"<b>Hello</b> <i>world</i>",
+        element_content_handlers=[
+            ElementContentHandler("b, i", end_tag=end_tag_handler)
+        ],
+    )
+
+    assert result == r"<b>Hello</strong> <i>world</em>"
+
+
+@pytest.mark.parametrize(
+    "name", ["", "div><script>alert(1)</script", b"div><script>alert(1)</script"]
+)
+def test_end_tag_set_invalid_name(name):
+    errors = []
+
+    def end_tag_handler(end, tag_name):
+        with pytest.raises(TagNameError) as e:
+            end.set_name(name)
+        errors.append(e.value)
+
+    result = rewrite_str(
+        r"<div>x</div>",
+        element_content_handlers=[
+            ElementContentHandler("div", end_tag=end_tag_handler)
+        ],
+    )
+
+    assert result == r"<div>x</div>"
+    assert len(errors) == 1
+
+
+def test_end_tag_replace_and_removed():
+    removed = []
+
+    def end_tag_handler(end, tag_name):
+        removed.append(end.removed)
+        end.replace("<!-- end -->", ContentType.Html)
+        removed.append(end.removed)
+
+    result = rewrite_str(
+        r"<div>Hello</div>",
+        element_content_handlers=[
+            ElementContentHandler("div", end_tag=end_tag_handler)
+        ],
+    )
+
+    assert result == r"<div>Hello<!-- end -->"
+    assert removed == [False, True]
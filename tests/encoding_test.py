@@ -0,0 +1,103 @@
+from lolhtml import (
+    ContentType,
+    EncodingError,
+    HtmlRewriter,
+    Rewriter,
+    ElementContentHandler,
+    rewrite_bytes,
+)
+import pytest
+
+
+def test_rewrite_utf8_bytes():
+    result = rewrite_bytes(
+        "<div>Hεllo</div>".encode("utf-8"),
+        element_content_handlers=[
+            ElementContentHandler(
+                "div", element=lambda elem: elem.append("!", ContentType.Text)
+            )
+        ],
+    )
+
+    assert result == "<div>Hεllo!</div>".encode("utf-8")
+
+
+@pytest.mark.parametrize(
+    "encoding",
+    ["shift_jis", "windows-1251", "iso-8859-1"],
+)
+def test_rewrite_preserves_encoding(encoding):
+    samples = {
+        "shift_jis": "こんにちは",
+        "windows-1251": "Привет",
+        "iso-8859-1": "Grüße",
+    }
+    text = samples[encoding]
+
+    def handler(elem):
+        elem.set_attribute("title", text)
+        elem.append(text, ContentType.Text)
+
+    result = rewrite_bytes(
+        f"<div>{text}</div>".encode(encoding),
+        encoding=encoding,
+        element_content_handlers=[ElementContentHandler("div", element=handler)],
+    )
+
+    assert result == f'<div title="{text}">{text}{text}</div>'.encode(encoding)
+
+
+def test_unknown_encoding():
+    with pytest.raises(EncodingError):
+        rewrite_bytes(b"<div></div>", encoding="no-such-encoding")
+
+
+def test_non_ascii_compatible_encoding():
+    with pytest.raises(EncodingError):
+        rewrite_bytes(b"<div></div>", encoding="utf-16le")
+
+    with pytest.raises(EncodingError):
+        HtmlRewriter(lambda chunk: None, encoding="iso-2022-jp")
+
+
+def test_streaming_bytes():
+    chunks = []
+    rewriter = HtmlRewriter(chunks.append, encoding="windows-1251")
+
+    data = "<div>Привет</div>".encode("windows-1251")
+    rewriter.write(data[:7])
+    rewriter.write(data[7:])
+    rewriter.end()
+
+    assert b"".join(chunks) == data
+
+
+def test_streaming_str_is_encoded():
+    chunks = []
+    rewriter = HtmlRewriter(chunks.append, encoding="windows-1251")
+
+    rewriter.write("<div>Привет</div>")
+    rewriter.end()
+
+    assert b"".join(chunks) == "<div>Привет</div>".encode("windows-1251")
+
+
+def test_unencodable_str():
+    with pytest.raises(UnicodeEncodeError) as excinfo:
+        Rewriter(encoding="windows-1252").rewrite("<p>café 日本</p>")
+
+    assert excinfo.value.encoding == "windows-1252"
+    assert (excinfo.value.start, excinfo.value.end) == (8, 9)
+
+    output = []
+    rewriter = HtmlRewriter(output.append, encoding="windows-1252")
+
+    with pytest.raises(UnicodeEncodeError):
+        rewriter.write("<p>日本</p>")
+
+    rewriter.write("<p>café</p>")
+    rewriter.end()
+
+    assert b"".join(output) == "<p>café</p>".encode("windows-1252")
+
+    assert Rewriter(encoding="windows-1252").rewrite("<p>café</p>") == "<p>café</p>"
@@ -0,0 +1,107 @@
+import pickle
+
+import lolhtml
+from lolhtml import (
+    AttributeNameError,
+    CommentTextError,
+    ContentHandlerError,
+    ElementContentHandler,
+    EncodingError,
+    EndTagError,
+    MemoryLimitExceededError,
+    ParsingAmbiguityError,
+    RewritableUnitExpiredError,
+    RewritingError,
+    RuleSetError,
+    SelectorError,
+    TagNameError,
+    rewrite_bytes,
+    rewrite_str,
+)
+import pytest
+
+
+@pytest.mark.parametrize(
+    "error",
+    [
+        ParsingAmbiguityError,
+        MemoryLimitExceededError,
+        ContentHandlerError,
+        SelectorError,
+        AttributeNameError,
+        CommentTextError,
+        EncodingError,
+        TagNameError,
+        EndTagError,
+    ],
+)
+def test_hierarchy(error):
+    assert issubclass(error, RewritingError)
+    assert issubclass(error, RuntimeError)
+
+
+@pytest.mark.parametrize(
+    "error",
+    [
+        RewritingError,
+        ParsingAmbiguityError,
+        MemoryLimitExceededError,
+        ContentHandlerError,
+        SelectorError,
+        AttributeNameError,
+        CommentTextError,
+        EncodingError,
+        TagNameError,
+        EndTagError,
+        RewritableUnitExpiredError,
+        RuleSetError,
+    ],
+)
+def test_names_and_pickling(error):
+    assert error.__module__ == "lolhtml"
+    assert getattr(lolhtml, error.__qualname__) is error
+
+    copy = pickle.loads(pickle.dumps(error("message")))
+
+    assert type(copy) is error
+    assert copy.args == ("message",)
+
+
+def test_parsing_ambiguity():
+    with pytest.raises(ParsingAmbiguityError, match="<xmp>"):
+        rewrite_str(
+            r'<select><xmp><script>"use strict";</script></select>',
+            element_content_handlers=[
+                ElementContentHandler("*", element=lambda el: None)
+            ],
+        )
+
+
+def test_attribute_name_error():
+    def handler(el):
+        with pytest.raises(AttributeNameError, match="forbidden"):
+            el.set_attribute("foo bar", "baz")
+
+        with pytest.raises(AttributeNameError, match="empty"):
+            el.set_attribute("", "baz")
+
+    rewrite_str(
+        r"<div></div>",
+        element_content_handlers=[ElementContentHandler("div", element=handler)],
+    )
+
+
+def test_encoding_error_message():
+    with pytest.raises(EncodingError, match="no-such-encoding"):
+        rewrite_bytes(b"<div></div>", encoding="no-such-encoding")
+
+
+def test_handler_exceptions_are_not_wrapped():
+    def handler(el):
+        raise KeyError("foo")
+
+    with pytest.raises(KeyError):
+        rewrite_str(
+            r"<div></div>",
+            element_content_handlers=[ElementContentHandler("div", element=handler)],
+        )
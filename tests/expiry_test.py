@@ -0,0 +1,93 @@
+from lolhtml import (
+    ContentType,
+    DocumentContentHandler,
+    ElementContentHandler,
+    RewritableUnitExpiredError,
+    rewrite_str,
+)
+import pytest
+
+
+def test_element_expires():
+    stored = []
+
+    rewrite_str(
+        r"<div></div>",
+        element_content_handlers=[ElementContentHandler("div", element=stored.append)],
+    )
+
+    [el] = stored
+    with pytest.raises(RewritableUnitExpiredError):
+        el.tag_name()
+    with pytest.raises(RewritableUnitExpiredError):
+        el.set_attribute("foo", "bar")
+    with pytest.raises(RewritableUnitExpiredError):
+        el.remove()
+
+
+def test_comment_and_text_chunk_expire():
+    comments = []
+    chunks = []
+
+    rewrite_str(
+        r"<div><!-- foo -->bar</div>",
+        element_content_handlers=[
+            ElementContentHandler("div", comments=comments.append, text=chunks.append)
+        ],
+    )
+
+    assert comments and chunks
+    with pytest.raises(RewritableUnitExpiredError):
+        comments[0].text()
+    for chunk in chunks:
+        with pytest.raises(RewritableUnitExpiredError):
+            chunk.as_str()
+        with pytest.raises(RewritableUnitExpiredError):
+            chunk.replace("baz", ContentType.Text)
+
+
+def test_end_tag_expires():
+    end_tags = []
+
+    rewrite_str(
+        r"<div></div>",
+        element_content_handlers=[
+            ElementContentHandler(
+                "div", element=lambda el: el.on_end_tag(end_tags.append)
+            )
+        ],
+    )
+
+    [end_tag] = end_tags
+    with pytest.raises(RewritableUnitExpiredError):
+        end_tag.name()
+
+
+def test_document_units_expire():
+    doctypes = []
+    ends = []
+
+    rewrite_str(
+        r"<!DOCTYPE html><div></div>",
+        document_content_handlers=[
+            DocumentContentHandler(doctype=doctypes.append, end=ends.append)
+        ],
+    )
+
+    with pytest.raises(RewritableUnitExpiredError):
+        doctypes[0].name()
+    with pytest.raises(RewritableUnitExpiredError):
+        ends[0].append("foo", ContentType.Text)
+
+
+def test_unit_is_usable_inside_handler():
+    def handler(el):
+        assert el.tag_name() == "div"
+        el.set_attribute("foo", "bar")
+
+    result = rewrite_str(
+        r"<div></div>",
+        element_content_handlers=[ElementContentHandler("div", element=handler)],
+    )
+
+    assert result == r'<div foo="bar"></div>'
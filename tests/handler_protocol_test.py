@@ -0,0 +1,98 @@
+from lolhtml import (
+    ContentType,
+    DocumentContentHandler,
+    ElementContentHandler,
+    rewrite_str,
+)
+
+
+class LinkCollector:
+    def __init__(self):
+        self.links = []
+
+    def element(self, el):
+        self.links.append(el.get_attribute("href"))
+
+    def end_tag(self, end, tag_name):
+        end.before("!", ContentType.Text)
+
+
+def test_handler_object():
+    collector = LinkCollector()
+
+    result = rewrite_str(
+        r'<a href="/foo">Foo</a><a href="/bar">Bar</a>',
+        element_content_handlers=[ElementContentHandler("a", collector)],
+    )
+
+    assert collector.links == ["/foo", "/bar"]
+    assert result == r'<a href="/foo">Foo!</a><a href="/bar">Bar!</a>'
+
+
+def test_explicit_callable_takes_precedence():
+    collector = LinkCollector()
+    elements = []
+
+    rewrite_str(
+        r'<a href="/foo"></a>',
+        element_content_handlers=[
+            ElementContentHandler("a", handler=collector, element=elements.append)
+        ],
+    )
+
+    assert collector.links == []
+    assert len(elements) == 1
+
+
+def test_element_content_handler_subclass():
+    class TextCounter(ElementContentHandler):
+        def __init__(self, selector):
+            self.count = 0
+
+        def text(self, chunk):
+            self.count += len(chunk.as_str())
+
+    counter = TextCounter("p")
+
+    rewrite_str(r"<p>Hello</p><div>world</div><p>!</p>", element_content_handlers=[counter])
+
+    assert counter.selector == "p"
+    assert counter.count == 6
+
+
+def test_document_content_handler_subclass():
+    class CommentStripper(DocumentContentHandler):
+        def __init__(self):
+            self.removed = 0
+
+        def comments(self, comment):
+            self.removed += 1
+            return False
+
+        def end(self, end):
+            end.append(f"<!-- {self.removed} removed -->", ContentType.Html)
+
+    stripper = CommentStripper()
+
+    result = rewrite_str(
+        r"<!-- a --><p><!-- b --></p>", document_content_handlers=[stripper]
+    )
+
+    assert result == r"<p></p><!-- 2 removed -->"
+
+
+def test_document_handler_object():
+    class Doctypes:
+        seen = None
+
+        def doctype(self, doctype):
+            self.seen = doctype.name()
+
+    doctypes = Doctypes()
+
+    rewrite_str(
+        r"<!DOCTYPE html>",
+        document_content_handlers=[DocumentContentHandler(handler=doctypes)],
+    )
+
+    assert doctypes.seen == "html"
@@ -0,0 +1,74 @@
+from lolhtml import HtmlRewriter, ElementContentHandler
+import pytest
+
+
+def test_write_and_end():
+    chunks = []
+
+    def modify_scheme(elem):
+        href = elem.get_attribute("href").replace("http:", "https:")
+        elem.set_attribute("href", href)
+
+    rewriter = HtmlRewriter(
+        chunks.append,
+        element_content_handlers=[ElementContentHandler("a", element=modify_scheme)],
+    )
+    rewriter.write(r"<div><a href=")
+    rewriter.write(r"http://example.com>")
+    rewriter.write(r"</a></div>")
+    rewriter.end()
+
+    assert all(isinstance(chunk, bytes) for chunk in chunks)
+    assert b"".join(chunks) == rb'<div><a href="https://example.com"></a></div>'
+
+
+def test_output_is_streamed():
+    chunks = []
+    rewriter = HtmlRewriter(chunks.append)
+
+    rewriter.write(r"<div>Hello</div>")
+    assert b"".join(chunks) == rb"<div>Hello</div>"
+
+    rewriter.write(r"<span>world</span>")
+    rewriter.end()
+    assert b"".join(chunks) == rb"<div>Hello</div><span>world</span>"
+
+
+def test_handler_error_propagates():
+    def handler(elem):
+        raise ValueError("boom")
+
+    rewriter = HtmlRewriter(
+        lambda chunk: None,
+        element_content_handlers=[ElementContentHandler("div", element=handler)],
+    )
+
+    with pytest.raises(ValueError):
+        rewriter.write(r"<div></div>")
+
+    with pytest.raises(RuntimeError):
+        rewriter.write(r"<div></div>")
+
+
+def test_output_sink_error_propagates():
+    def sink(chunk):
+        raise ValueError("sink failed")
+
+    rewriter = HtmlRewriter(sink)
+
+    with pytest.raises(ValueError):
+        rewriter.write(r"<div></div>")
+        rewriter.end()
+
+
+def test_write_after_end():
+    chunks = []
+    rewriter = HtmlRewriter(chunks.append)
+    rewriter.write(r"<div></div>")
+    rewriter.end()
+
+    with pytest.raises(RuntimeError):
+        rewriter.write(r"<div></div>")
+
+    with pytest.raises(RuntimeError):
+        rewriter.end()
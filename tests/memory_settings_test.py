@@ -0,0 +1,65 @@
+from lolhtml import (
+    ElementContentHandler,
+    HtmlRewriter,
+    MemoryLimitExceededError,
+    rewrite_str,
+)
+import pytest
+
+MAX = 100
+
+
+def create_rewriter(max_allowed_memory_usage):
+    return HtmlRewriter(
+        lambda chunk: None,
+        element_content_handlers=[ElementContentHandler("*", element=lambda el: None)],
+        max_allowed_memory_usage=max_allowed_memory_usage,
+        preallocated_parsing_buffer_size=0,
+    )
+
+
+def test_buffer_capacity_limit():
+    rewriter = create_rewriter(MAX)
+
+    # Use two chunks for the stream to force the usage of the buffer and
+    # make sure to overflow it.
+    rewriter.write('<img alt="' + "l" * (MAX // 2))
+
+    with pytest.raises(MemoryLimitExceededError):
+        rewriter.write("r" * (MAX // 2) + '" />')
+
+
+def test_rewrite_str_limit():
+    with pytest.raises(MemoryLimitExceededError):
+        rewrite_str(
+            '<img alt="' + "l" * MAX,
+            element_content_handlers=[
+                ElementContentHandler("*", element=lambda el: None)
+            ],
+            max_allowed_memory_usage=MAX,
+            preallocated_parsing_buffer_size=0,
+        )
+
+
+def test_within_limit():
+    result = rewrite_str(
+        '<img alt="foo">',
+        element_content_handlers=[ElementContentHandler("*", element=lambda el: None)],
+        max_allowed_memory_usage=MAX,
+        preallocated_parsing_buffer_size=0,
+    )
+
+    assert result == '<img alt="foo">'
+
+
+def test_memory_limit_is_a_runtime_error():
+    assert issubclass(MemoryLimitExceededError, RuntimeError)
+
+
+def test_preallocated_buffer_exceeds_limit():
+    with pytest.raises(ValueError):
+        rewrite_str(
+            "<div></div>",
+            max_allowed_memory_usage=10,
+            preallocated_parsing_buffer_size=20,
+        )
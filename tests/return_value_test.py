@@ -0,0 +1,82 @@
+from lolhtml import (
+    DocumentContentHandler,
+    ElementContentHandler,
+    rewrite_str,
+)
+import pytest
+
+
+class Markup(str):
+    def __html__(self):
+        return self
+
+
+def rewrite_with(html: str, **handlers) -> str:
+    return rewrite_str(
+        html, element_content_handlers=[ElementContentHandler("*", **handlers)]
+    )
+
+
+def test_none_is_noop():
+    assert rewrite_with(r"<div>Hi</div>", element=lambda el: None) == r"<div>Hi</div>"
+
+
+def test_false_removes():
+    result = rewrite_with(
+        r"<div><script>alert(1)</script><!-- c -->Hi</div>",
+        element=lambda el: False if el.tag_name() == "script" else None,
+        comments=lambda comment: False,
+    )
+
+    assert result == r"<div>Hi</div>"
+
+
+def test_str_replaces_as_text():
+    result = rewrite_with(
+        r"<div><b>Hi</b></div>",
+        element=lambda el: "<b>" if el.tag_name() == "b" else None,
+    )
+
+    assert result == r"<div>&lt;b&gt;</div>"
+
+
+def test_html_replaces_as_html():
+    result = rewrite_with(
+        r"<p>Hi</p>",
+        text=lambda text: Markup("<i>Bye</i>") if text.as_str() else None,
+    )
+
+    assert result == r"<p><i>Bye</i></p>"
+
+
+def test_end_tag_return_value():
+    result = rewrite_with(
+        r"<div>Hi</div>",
+        end_tag=lambda end, tag_name: Markup("</section>"),
+    )
+
+    assert result == r"<div>Hi</section>"
+
+
+def test_unsupported_return_value():
+    with pytest.raises(TypeError):
+        rewrite_with(r"<div></div>", element=lambda el: 42)
+
+    with pytest.raises(TypeError):
+        rewrite_with(r"<div></div>", element=lambda el: True)
+
+
+def test_unsupported_unit():
+    with pytest.raises(TypeError):
+        rewrite_str(
+            r"<!DOCTYPE html>",
+            document_content_handlers=[DocumentContentHandler(end=lambda end: False)],
+        )
+
+    assert (
+        rewrite_str(
+            r"<!DOCTYPE html>",
+            document_content_handlers=[DocumentContentHandler(end=lambda end: None)],
+        )
+        == r"<!DOCTYPE html>"
+    )
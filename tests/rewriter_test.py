@@ -0,0 +1,115 @@
+from lolhtml import (
+    ContentType,
+    ElementContentHandler,
+    HtmlRewriter,
+    Rewriter,
+    SelectorError,
+)
+import pytest
+
+
+def add_rel(el):
+    el.set_attribute("rel", "noopener")
+
+
+def test_rewrite_is_reusable():
+    rewriter = Rewriter(
+        element_content_handlers=[ElementContentHandler("a", element=add_rel)]
+    )
+
+    for i in range(3):
+        result = rewriter.rewrite(f'<a href="/{i}"></a>')
+        assert result == f'<a href="/{i}" rel="noopener"></a>'
+
+
+def test_rewrite_bytes():
+    rewriter = Rewriter(
+        element_content_handlers=[
+            ElementContentHandler(
+                "div", element=lambda el: el.append("Привет", ContentType.Text)
+            )
+        ],
+        encoding="windows-1251",
+    )
+
+    assert rewriter.rewrite("<div></div>".encode("windows-1251")) == (
+        "<div>Привет</div>".encode("windows-1251")
+    )
+    assert rewriter.rewrite("<div></div>") == "<div>Привет</div>"
+
+
+def test_stream():
+    rewriter = Rewriter(
+        element_content_handlers=[ElementContentHandler("a", element=add_rel)]
+    )
+
+    for _ in range(2):
+        chunks = []
+        stream = rewriter.stream(chunks.append)
+        assert isinstance(stream, HtmlRewriter)
+
+        stream.write("<a href=")
+        stream.write("/foo></a>")
+        stream.end()
+
+        assert b"".join(chunks) == b'<a href=/foo rel="noopener"></a>'
+
+
+def test_settings_are_validated_once():
+    with pytest.raises(ValueError):
+        Rewriter(max_allowed_memory_usage=10, preallocated_parsing_buffer_size=20)
+
+
+def test_decorators():
+    rw = Rewriter()
+    seen = []
+
+    @rw.element("a[href]")
+    def add_rel(el):
+        el.set_attribute("rel", "noopener")
+
+    @rw.end_tag("p")
+    def mark_end(end, tag_name):
+        end.before("!", ContentType.Text)
+
+    @rw.text("p", text_mode="node")
+    def shout(text):
+        if text.as_str():
+            return text.as_str().upper()
+
+    @rw.comments()
+    def strip_comments(comment):
+        return False
+
+    @rw.doctype
+    def collect_doctype(doctype):
+        seen.append(doctype.name())
+
+    @rw.document_end
+    def add_footer(end):
+        end.append("<footer></footer>", ContentType.Html)
+
+    assert add_rel.__name__ == "add_rel"
+
+    result = rw.rewrite(
+        r'<!DOCTYPE html><!-- x --><p>hi <a href="/">there</a></p><a>no href</a>'
+    )
+
+    assert seen == ["html"]
+    assert result == (
+        r'<!DOCTYPE html><p>HI <a href="/" rel="noopener">THERE</a>!</p>'
+        r"<a>no href</a><footer></footer>"
+    )
+
+
+def test_decorator_with_invalid_selector():
+    rw = Rewriter()
+
+    with pytest.raises(SelectorError):
+        rw.element("div[")
+
+    with pytest.raises(ValueError):
+
+        @rw.text("p", text_mode="word")
+        def handler(text):
+            pass
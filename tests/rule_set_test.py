@@ -0,0 +1,169 @@
+import json
+
+from lolhtml import ElementContentHandler, Rewriter, RuleSetError
+import pytest
+
+RULES = [
+    {"selector": "a", "action": "set_attribute", "name": "rel", "value": "noopener"},
+    {"selector": "script", "action": "remove_element"},
+    {"selector": "b", "action": "rename_tag", "name": "strong"},
+    {
+        "selector": "body",
+        "action": "append",
+        "content": "<footer></footer>",
+        "content_type": "html",
+    },
+]
+
+HTML = "<body><a>x</a><script>y</script><b>z</b></body>"
+EXPECTED = '<body><a rel="noopener">x</a><strong>z</strong><footer></footer></body>'
+
+
+def test_from_dict():
+    assert Rewriter.from_rules({"rules": RULES}).rewrite(HTML) == EXPECTED
+
+
+def test_from_list():
+    assert Rewriter.from_rules(RULES).rewrite(HTML) == EXPECTED
+
+
+def test_from_json(tmp_path):
+    path = tmp_path / "rules.json"
+    path.write_text(json.dumps({"rules": RULES}))
+
+    assert Rewriter.from_rules(path).rewrite(HTML) == EXPECTED
+    assert Rewriter.from_rules(str(path)).rewrite(HTML) == EXPECTED
+
+
+def test_from_toml(tmp_path):
+    path = tmp_path / "rules.toml"
+    path.write_text(
+        """
+[[rules]]
+selector = "a"
+action = "set_attribute"
+name = "rel"
+value = "noopener"
+
+[[rules]]
+selector = "script"
+action = "remove_element"
+
+[[rules]]
+selector = "b"
+action = "rename_tag"
+name = "strong"
+
+[[rules]]
+selector = "body"
+action = "append"
+content = "<footer></footer>"
+content_type = "html"
+"""
+    )
+
+    assert Rewriter.from_rules(path).rewrite(HTML) == EXPECTED
+
+
+def test_from_yaml(tmp_path):
+    path = tmp_path / "rules.yml"
+    path.write_text(
+        """
+rules:
+  - selector: a
+    action: set_attribute
+    name: rel
+    value: noopener
+  - selector: script
+    action: remove_element
+  - selector: b
+    action: rename_tag
+    name: strong
+  - selector: body
+    action: append
+    content: <footer></footer>
+    content_type: html
+"""
+    )
+
+    assert Rewriter.from_rules(path).rewrite(HTML) == EXPECTED
+
+
+def test_settings_and_handlers():
+    rewriter = Rewriter.from_rules(
+        [{"selector": "a", "action": "remove_attribute", "name": "rel"}],
+        element_content_handlers=[
+            ElementContentHandler("a", element=lambda el: el.set_attribute("id", "x"))
+        ],
+        encoding="windows-1251",
+    )
+
+    assert rewriter.rewrite('<a rel="x">Ы</a>'.encode("windows-1251")) == (
+        '<a id="x">Ы</a>'.encode("windows-1251")
+    )
+
+
+@pytest.mark.parametrize(
+    "rules, index, field",
+    [
+        ([{"selector": "a"}], 0, "action"),
+        ([RULES[0], {"action": "unwrap"}], 1, "selector"),
+        ([RULES[0], RULES[1], {"selector": "a >", "action": "unwrap"}], 2, "selector"),
+        ([{"selector": "a", "action": "explode"}], 0, "action"),
+        ([{"selector": "a", "action": "unwrap", "name": "x"}], 0, "name"),
+        ([{"selector": "a", "action": "set_attribute", "name": "x"}], 0, "value"),
+        (
+            [{"selector": "a", "action": "set_attribute", "name": "a b", "value": ""}],
+            0,
+            "name",
+        ),
+        ([{"selector": "a", "action": "rename_tag", "name": "1"}], 0, "name"),
+        ([{"selector": "a", "action": "remove_attribute", "name": 1}], 0, "name"),
+        (
+            [{"selector": "a", "action": "append", "content": "", "content_type": "x"}],
+            0,
+            "content_type",
+        ),
+        ([None], 0, None),
+        ({"rules": RULES, "extra": 1}, None, "extra"),
+        ({"rules": {}}, None, "rules"),
+    ],
+)
+def test_invalid_rules(rules, index, field):
+    with pytest.raises(RuleSetError) as e:
+        Rewriter.from_rules(rules)
+
+    assert e.value.index == index
+    assert e.value.field == field
+
+    if index is not None and field is not None:
+        assert str(e.value).startswith(f"rules[{index}].{field}: ")
+
+
+def test_invalid_file(tmp_path):
+    path = tmp_path / "rules.json"
+    path.write_text("{")
+
+    with pytest.raises(RuleSetError):
+        Rewriter.from_rules(path)
+
+    with pytest.raises(ValueError, match="format"):
+        Rewriter.from_rules(tmp_path / "rules.txt")
+
+    with pytest.raises(FileNotFoundError):
+        Rewriter.from_rules(tmp_path / "missing.json")
+
+
+def test_unreadable_file_names_the_path(tmp_path):
+    path = tmp_path / "missing.yaml"
+
+    with pytest.raises(FileNotFoundError, match="missing.yaml") as e:
+        Rewriter.from_rules(path)
+
+    assert e.value.filename == str(path)
+
+    path = tmp_path / "rules.yaml"
+    path.write_bytes(b"\xff\xfe")
+
+    with pytest.raises(OSError, match="rules.yaml"):
+        Rewriter.from_rules(path)
@@ -0,0 +1,114 @@
+from lolhtml import (
+    Append,
+    AttributeNameError,
+    ContentType,
+    ElementContentHandler,
+    InsertAfter,
+    InsertBefore,
+    Prepend,
+    RemoveAttribute,
+    RemoveElement,
+    RenameTag,
+    ReplaceWith,
+    Rewriter,
+    Rule,
+    SelectorError,
+    SetAttribute,
+    TagNameError,
+    Unwrap,
+    rewrite_str,
+)
+import pytest
+
+
+def rewrite(html, *rules):
+    return rewrite_str(html, element_content_handlers=list(rules))
+
+
+def test_set_attribute():
+    assert (
+        rewrite('<a href="/">x</a><b>y</b>', SetAttribute("a", "rel", "noopener"))
+        == '<a href="/" rel="noopener">x</a><b>y</b>'
+    )
+
+
+def test_remove_attribute():
+    assert rewrite('<p style="x" id="a"></p>', RemoveAttribute("p", "style")) == (
+        '<p id="a"></p>'
+    )
+
+
+def test_remove_element():
+    assert rewrite("<div><script>x</script>y</div>", RemoveElement("script")) == (
+        "<div>y</div>"
+    )
+
+
+def test_rename_tag():
+    assert rewrite("<b>x</b>", RenameTag("b", "strong")) == "<strong>x</strong>"
+
+
+def test_insertions():
+    assert (
+        rewrite(
+            "<div>x</div>",
+            InsertBefore("div", "<hr>", ContentType.Html),
+            InsertAfter("div", "<hr>", ContentType.Text),
+            Prepend("div", "<i>a</i>", ContentType.Html),
+            Append("div", "&", ContentType.Text),
+        )
+        == "<hr><div><i>a</i>x&amp;</div>&lt;hr&gt;"
+    )
+
+
+def test_replace_with():
+    rule = ReplaceWith("ins", "<b>new</b>", ContentType.Html)
+
+    assert rewrite("<p><ins>old</ins></p>", rule) == "<p><b>new</b></p>"
+
+
+def test_unwrap():
+    assert rewrite("<p><span>a <b>b</b></span></p>", Unwrap("span")) == (
+        "<p>a <b>b</b></p>"
+    )
+
+
+def test_mixed_with_python_handlers():
+    def mark(el):
+        el.set_attribute("data-rel", el.get_attribute("rel"))
+
+    rewriter = Rewriter(
+        element_content_handlers=[
+            SetAttribute("a", "rel", "nofollow"),
+            ElementContentHandler("a", element=mark),
+            RemoveAttribute("a", "rel"),
+        ]
+    )
+
+    assert rewriter.rewrite("<a>x</a>") == '<a data-rel="nofollow">x</a>'
+
+
+def test_rules_are_rules():
+    rule = Unwrap("div > span")
+
+    assert isinstance(rule, Rule)
+    assert rule.selector == "div > span"
+
+
+def test_invalid_rules():
+    with pytest.raises(SelectorError):
+        RemoveElement("div >")
+
+    with pytest.raises(AttributeNameError):
+        SetAttribute("a", "re l", "x")
+
+    with pytest.raises(AttributeNameError):
+        SetAttribute("a", "", "x")
+
+    with pytest.raises(TagNameError):
+        RenameTag("b", "1b")
+
+
+def test_invalid_handler():
+    with pytest.raises(TypeError):
+        rewrite_str("<p></p>", element_content_handlers=[object()])
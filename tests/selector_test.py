@@ -0,0 +1,44 @@
+from lolhtml import ElementContentHandler, SelectorError, rewrite_str
+import pytest
+
+
+@pytest.mark.parametrize(
+    ("selector", "variant"),
+    [
+        ("a:hover", "UnsupportedPseudoClassOrElement"),
+        ("div >", "DanglingCombinator"),
+        ("", "EmptySelector"),
+        ("div ~ span", "UnsupportedCombinator"),
+        ("[foo=]", "UnexpectedEnd"),
+        ("div|a", "NamespacedSelector"),
+    ],
+)
+def test_invalid_selector(selector, variant):
+    with pytest.raises(SelectorError) as exc_info:
+        ElementContentHandler(selector, element=lambda el: None)
+
+    message = str(exc_info.value)
+    assert f"`{selector}`" in message
+    assert variant in message
+
+
+def test_valid_selector_is_reusable():
+    handler = ElementContentHandler(
+        "a[href]", element=lambda el: el.set_attribute("rel", "nofollow")
+    )
+    assert handler.selector == "a[href]"
+
+    for _ in range(2):
+        result = rewrite_str(
+            r'<a href="/">home</a><a>anchor</a>',
+            element_content_handlers=[handler],
+        )
+        assert result == r'<a href="/" rel="nofollow">home</a><a>anchor</a>'
+
+
+def test_selector_keyword_argument():
+    handler = ElementContentHandler(selector="div", element=lambda el: el.remove())
+
+    assert rewrite_str(r"<div></div><p></p>", element_content_handlers=[handler]) == (
+        r"<p></p>"
+    )
@@ -0,0 +1,114 @@
+from lolhtml import (
+    ElementContentHandler,
+    HtmlRewriter,
+    ParsingAmbiguityError,
+    Rewriter,
+    rewrite_bytes,
+    rewrite_str,
+)
+import pytest
+
+AMBIGUOUS_HTML = "<select><xmp><script>alert(1)</script></xmp></select>"
+
+
+def test_strict_by_default():
+    with pytest.raises(ParsingAmbiguityError):
+        rewrite_str(AMBIGUOUS_HTML)
+
+    with pytest.raises(ParsingAmbiguityError):
+        rewrite_bytes(AMBIGUOUS_HTML.encode())
+
+
+def test_non_strict():
+    assert rewrite_str(AMBIGUOUS_HTML, strict=False) == AMBIGUOUS_HTML
+    assert rewrite_bytes(AMBIGUOUS_HTML.encode(), strict=False) == AMBIGUOUS_HTML.encode()
+    assert Rewriter(strict=False).rewrite(AMBIGUOUS_HTML) == AMBIGUOUS_HTML
+
+
+def test_non_strict_streaming():
+    chunks = []
+    rewriter = HtmlRewriter(chunks.append, strict=False)
+    rewriter.write(AMBIGUOUS_HTML)
+    rewriter.end()
+
+    assert b"".join(chunks) == AMBIGUOUS_HTML.encode()
+
+
+def test_ambiguities_are_reported():
+    ambiguities = []
+    html = "<select><title></title></select><title></title><frameset><style></style>"
+
+    rewrite_str(html, strict=False, on_parsing_ambiguity=ambiguities.append)
+
+    assert all(isinstance(e, ParsingAmbiguityError) for e in ambiguities)
+    assert [e.tag_name for e in ambiguities] == ["title", "style"]
+    assert "<title>" in str(ambiguities[0])
+
+
+def test_unambiguous_markup_in_select():
+    ambiguities = []
+    html = (
+        "<select><script></script><template><template></template><title></title>"
+        "</template><textarea></textarea></select><noframes></noframes>"
+    )
+
+    rewrite_str(html, strict=False, on_parsing_ambiguity=ambiguities.append)
+
+    assert [e.tag_name for e in ambiguities] == ["title"]
+
+    with pytest.raises(ParsingAmbiguityError):
+        rewrite_str(html)
+
+
+def test_reporting_requires_non_strict_mode():
+    with pytest.raises(ValueError):
+        rewrite_str("<div></div>", on_parsing_ambiguity=print)
+
+
+def test_reporting_error_propagates():
+    def report(e):
+        raise ValueError("ambiguous")
+
+    rewriter = HtmlRewriter(lambda chunk: None, strict=False, on_parsing_ambiguity=report)
+
+    with pytest.raises(ValueError):
+        rewriter.write(AMBIGUOUS_HTML)
+
+
+def test_reports_name_the_context():
+    ambiguities = []
+    html = "<select><title></title><template><style></style></template></select>"
+
+    rewrite_str(html, strict=False, on_parsing_ambiguity=ambiguities.append)
+
+    assert [(e.tag_name, e.context) for e in ambiguities] == [
+        ("title", "select"),
+        ("style", "template"),
+    ]
+    assert "<select>" in str(ambiguities[0])
+
+
+@pytest.mark.parametrize(
+    "handler",
+    [
+        ElementContentHandler("select, template", end_tag=lambda end, name: None),
+        ElementContentHandler(
+            "select, template", element=lambda el: el.on_end_tag(lambda end: None)
+        ),
+    ],
+)
+def test_user_end_tag_handlers_dont_break_tracking(handler):
+    html = (
+        "<select><template></template></select><title></title>"
+        "<select><template><title></title></template></select><title></title>"
+    )
+    ambiguities = []
+
+    rewrite_str(
+        html,
+        strict=False,
+        on_parsing_ambiguity=ambiguities.append,
+        element_content_handlers=[handler],
+    )
+
+    assert [(e.tag_name, e.context) for e in ambiguities] == [("title", "template")]
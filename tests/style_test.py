@@ -0,0 +1,231 @@
+from collections.abc import Mapping, MutableMapping
+
+from lolhtml import ElementContentHandler, rewrite_str
+import pytest
+
+
+def rewrite_style(html: str, handler) -> str:
+    return rewrite_str(
+        html, element_content_handlers=[ElementContentHandler("div", element=handler)]
+    )
+
+
+def test_read():
+    def handler(el):
+        style = el.style
+
+        assert list(style) == ["color", "background", "--Accent"]
+        assert len(style) == 3
+        assert style["color"] == "blue"
+        assert style["COLOR"] == "blue"
+        assert style["background"] == "url('a;b.png') no-repeat"
+        assert style.get("--Accent") == "red"
+        assert style.get("--accent") is None
+        assert style.get("margin", "0") == "0"
+        assert style.priority("color") == ""
+        assert style.priority("background") == "important"
+        assert "margin" not in style
+
+        with pytest.raises(KeyError):
+            style["margin"]
+
+    rewrite_style(
+        """<div style="color: red; background: url('a;b.png') no-repeat !IMPORTANT;"""
+        """ COLOR:blue; --Accent: red">""",
+        handler,
+    )
+
+
+def test_important_declaration_wins():
+    def handler(el):
+        assert el.style["color"] == "red"
+        assert el.style.priority("color") == "important"
+
+    rewrite_style(r'<div style="color: red !important; color: blue">', handler)
+
+
+def test_set_preserves_other_declarations():
+    def handler(el):
+        el.style["position"] = "static"
+        el.style.set("z-index", "10", important=True)
+
+    result = rewrite_style(
+        r'<div style="position:fixed;top :0;/* keep */ left: calc(1px + 2px)">',
+        handler,
+    )
+
+    assert result == (
+        r'<div style="position: static;top :0;/* keep */ left: calc(1px + 2px); '
+        r'z-index: 10 !important">'
+    )
+
+
+@pytest.mark.parametrize(
+    "style, expected",
+    [
+        ("color:red;margin:0;", "color:red;margin:0; top: 0;"),
+        ("color:red ;margin:0", "color:red ;margin:0; top: 0"),
+        (" color:red;;margin:0 ", " color:red;;margin:0 ; top: 0"),
+    ],
+)
+def test_set_preserves_separators(style, expected):
+    def handler(el):
+        el.style["top"] = "0"
+
+    assert rewrite_style(f'<div style="{style}">', handler) == (
+        f'<div style="{expected}">'
+    )
+
+
+@pytest.mark.parametrize(
+    "name, expected",
+    [
+        ("color", "margin:0;\ttop: 1px;"),
+        ("margin", "color: red;\ttop: 1px;"),
+        ("top", "color: red;margin:0;"),
+    ],
+)
+def test_remove_preserves_separators(name, expected):
+    def handler(el):
+        del el.style[name]
+
+    assert rewrite_style('<div style="color: red;margin:0;\ttop: 1px;">', handler) == (
+        f'<div style="{expected}">'
+    )
+
+
+def test_set_replaces_duplicates():
+    def handler(el):
+        el.style["color"] = "green"
+
+    result = rewrite_style(r'<div style="color: red; margin: 0; color: blue">', handler)
+
+    assert result == r'<div style="color: green; margin: 0">'
+
+
+def test_remove():
+    def handler(el):
+        assert el.style.remove("position") == "fixed"
+        assert el.style.remove("missing") is None
+
+        del el.style["top"]
+
+        with pytest.raises(KeyError):
+            del el.style["top"]
+
+    result = rewrite_style(r'<div style="position: fixed; top: 0">', handler)
+
+    assert result == r'<div style="">'
+
+
+def test_no_style_attribute():
+    def handler(el):
+        assert len(el.style) == 0
+        assert el.style.remove("color") is None
+
+    assert rewrite_style(r"<div>", handler) == r"<div>"
+
+
+def test_invalid_declarations():
+    def handler(el):
+        with pytest.raises(ValueError):
+            el.style["color"] = "red; position: fixed"
+
+        with pytest.raises(ValueError):
+            el.style["color"] = "red !important"
+
+        with pytest.raises(ValueError):
+            el.style["a:b"] = "red"
+
+    rewrite_style(r"<div>", handler)
+
+
+@pytest.mark.parametrize(
+    "value", ["url(x", "url('x)", '"x', "attr(x[", "calc(1px))", "/* x", "x\\"]
+)
+def test_unbalanced_values(value):
+    def handler(el):
+        with pytest.raises(ValueError, match="unclosed"):
+            el.style["background"] = value
+
+        el.style["color"] = "red"
+
+    result = rewrite_style(r'<div style="margin: 0">', handler)
+
+    assert result == r'<div style="margin: 0; color: red">'
+
+
+def test_comments_before_names():
+    def handler(el):
+        assert list(el.style) == ["color", "margin"]
+        assert el.style["color"] == "red"
+
+        el.style["margin"] = "1px"
+
+    result = rewrite_style(
+        r'<div style="/* theme */ color: red; margin/* x */: 0">', handler
+    )
+
+    assert result == r'<div style="/* theme */ color: red; margin: 1px">'
+
+
+def test_mapping():
+    def handler(el):
+        style = el.style
+
+        assert isinstance(style, Mapping)
+        assert isinstance(style, MutableMapping)
+        assert style == {"color": "red", "margin": "0"}
+        assert style != {"color": "red"}
+        assert style != [("color", "red"), ("margin", "0")]
+
+        keys, values, items = style.keys(), style.values(), style.items()
+
+        assert list(values) == ["red", "0"]
+
+        style["top"] = "1px"
+
+        assert list(keys) == ["color", "margin", "top"]
+        assert ("top", "1px") in items
+
+    rewrite_style(r'<div style="color: red; margin: 0">', handler)
+
+
+def test_mapping_mutations():
+    def handler(el):
+        style = el.style
+
+        assert style.pop("color") == "blue"
+        assert style.pop("color", None) is None
+
+        with pytest.raises(KeyError):
+            style.pop("color")
+
+        style.update({"top": "0"}, left="1px")
+        style.update([("top", "2px")])
+
+        assert style.setdefault("top", "3px") == "2px"
+        assert style.setdefault("width", "10px") == "10px"
+        assert style.popitem() == ("width", "10px")
+
+        assert dict(style) == {"margin": "0", "top": "2px", "left": "1px"}
+
+    result = rewrite_style(
+        r'<div style="color: red; margin: 0; color: blue">', handler
+    )
+
+    assert result == r'<div style="margin: 0; top: 2px; left: 1px">'
+
+
+def test_clear():
+    def handler(el):
+        el.style.clear()
+
+        assert len(el.style) == 0
+
+        with pytest.raises(KeyError):
+            el.style.popitem()
+
+    assert rewrite_style(r'<div style="color: red; margin: 0">', handler) == (
+        r'<div style="">'
+    )
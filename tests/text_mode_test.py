@@ -0,0 +1,137 @@
+from lolhtml import (
+    ContentType,
+    DocumentContentHandler,
+    ElementContentHandler,
+    HtmlRewriter,
+    MemoryLimitExceededError,
+    TextType,
+)
+import pytest
+
+
+class Markup(str):
+    def __html__(self):
+        return self
+
+
+def rewrite_in_chunks(chunks, **kwargs) -> bytes:
+    output = []
+    rewriter = HtmlRewriter(output.append, **kwargs)
+
+    for chunk in chunks:
+        rewriter.write(chunk)
+    rewriter.end()
+
+    return b"".join(output)
+
+
+def test_node_mode_sees_whole_text():
+    texts = []
+
+    def handler(text):
+        assert text.last_in_text_node()
+        texts.append(text.as_str())
+
+    output = rewrite_in_chunks(
+        ["<p>Hel", "lo wo", "rld</p><p>&amp; more</p>"],
+        element_content_handlers=[
+            ElementContentHandler("p", text=handler, text_mode="node")
+        ],
+    )
+
+    assert texts == ["Hello world", "&amp; more"]
+    assert output == b"<p>Hello world</p><p>&amp; more</p>"
+
+
+def test_node_mode_replace():
+    def handler(text):
+        text.replace(text.as_str().replace("world", "there"), ContentType.Text)
+
+    output = rewrite_in_chunks(
+        ["<p>Hello wo", "rld</p>"],
+        element_content_handlers=[
+            ElementContentHandler("p", text=handler, text_mode="node")
+        ],
+    )
+
+    assert output == b"<p>Hello there</p>"
+
+
+def test_node_mode_returned_markup_keeps_character_references():
+    def handler(text):
+        return Markup(text.as_str().replace("fish", "cod"))
+
+    output = rewrite_in_chunks(
+        ["<p>fish &amp", "; chips &lt;3</p><p>&quot;fish&quot;</p>"],
+        element_content_handlers=[
+            ElementContentHandler("p", text=handler, text_mode="node")
+        ],
+    )
+
+    assert output == b"<p>cod &amp; chips &lt;3</p><p>&quot;cod&quot;</p>"
+
+
+@pytest.mark.parametrize("text_mode", ["chunk", "node"])
+def test_returned_text_is_escaped(text_mode):
+    output = rewrite_in_chunks(
+        ["<p>fish &amp; chips</p>"],
+        element_content_handlers=[
+            ElementContentHandler(
+                "p",
+                text=lambda text: "<img src=x> & b" if text.as_str() else None,
+                text_mode=text_mode,
+            )
+        ],
+    )
+
+    assert output == b"<p>&lt;img src=x&gt; &amp; b</p>"
+
+
+def test_node_mode_remove_and_insert():
+    def handler(text):
+        if text.as_str() == "remove me":
+            text.remove()
+        else:
+            text.before("[", ContentType.Text)
+            text.after("]", ContentType.Text)
+
+    output = rewrite_in_chunks(
+        ["<p>remo", "ve me</p><b>ke", "ep</b>"],
+        document_content_handlers=[
+            DocumentContentHandler(
+                text=handler, text_types=[TextType.Data], text_mode="node"
+            )
+        ],
+    )
+
+    assert output == b"<p></p><b>[keep]</b>"
+
+
+def test_chunk_mode_is_default():
+    texts = []
+
+    rewrite_in_chunks(
+        ["<p>Hel", "lo</p>"],
+        element_content_handlers=[
+            ElementContentHandler("p", text=lambda text: texts.append(text.as_str()))
+        ],
+    )
+
+    assert texts == ["Hel", "lo", ""]
+
+
+def test_max_text_node_size():
+    with pytest.raises(MemoryLimitExceededError):
+        rewrite_in_chunks(
+            ["<p>" + "a" * 10, "a" * 10 + "</p>"],
+            element_content_handlers=[
+                ElementContentHandler(
+                    "p", text=lambda text: None, text_mode="node", max_text_node_size=15
+                )
+            ],
+        )
+
+
+def test_unknown_text_mode():
+    with pytest.raises(ValueError):
+        ElementContentHandler("p", text=lambda text: None, text_mode="word")
@@ -0,0 +1,73 @@
+from lolhtml import (
+    DocumentContentHandler,
+    ElementContentHandler,
+    TextType,
+    rewrite_str,
+)
+
+
+def collect_text_types(html: str) -> list:
+    text_types = []
+
+    def handler(text):
+        if text.as_str():
+            text_types.append(text.text_type())
+
+    rewrite_str(html, document_content_handlers=[DocumentContentHandler(text=handler)])
+
+    return text_types
+
+
+def test_text_types():
+    assert collect_text_types(
+        r"<p>a</p><title>b</title><style>c</style><script>d</script>"
+        r"<svg><![CDATA[e]]></svg><plaintext>f"
+    ) == [
+        TextType.Data,
+        TextType.RCData,
+        TextType.RawText,
+        TextType.ScriptData,
+        TextType.CDataSection,
+        TextType.PlainText,
+    ]
+
+
+def test_text_type_comparison_and_hashing():
+    assert TextType.Data == TextType.Data
+    assert TextType.Data != TextType.RawText
+    assert len({TextType.Data, TextType.Data, TextType.ScriptData}) == 2
+    assert {TextType.Data: "data"}[collect_text_types("a")[0]] == "data"
+    assert repr(TextType.RCData) == "TextType.RCData"
+
+
+def test_element_text_types_filter():
+    chunks = []
+
+    rewrite_str(
+        r"<div>a<script>b</script><style>c</style><textarea>d</textarea></div>",
+        element_content_handlers=[
+            ElementContentHandler(
+                "div",
+                text=lambda text: chunks.append(text.as_str()),
+                text_types=[TextType.Data, TextType.RCData],
+            )
+        ],
+    )
+
+    assert "".join(chunks) == "ad"
+
+
+def test_document_text_types_filter():
+    chunks = []
+
+    rewrite_str(
+        r"<p>a</p><script>b</script>",
+        document_content_handlers=[
+            DocumentContentHandler(
+                text=lambda text: chunks.append(text.as_str()),
+                text_types=[TextType.ScriptData],
+            )
+        ],
+    )
+
+    assert "".join(chunks) == "b"
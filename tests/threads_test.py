@@ -0,0 +1,85 @@
+from concurrent.futures import ThreadPoolExecutor
+import threading
+import time
+
+from lolhtml import (
+    ElementContentHandler,
+    ParsingAmbiguityError,
+    Rewriter,
+    SetAttribute,
+    rewrite_str,
+)
+import pytest
+
+
+def make_document(i):
+    return f"<div id={i}>" + "<p>text <b>node</b></p>" * 1000 + "</div>"
+
+
+def test_parallel_rewrites_without_handlers():
+    documents = [make_document(i) for i in range(16)]
+
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(rewrite_str, documents))
+
+    assert results == documents
+
+
+def test_parallel_rewrites_with_handlers():
+    def add_class(el):
+        el.set_attribute("class", "x")
+
+    rewriter = Rewriter(
+        element_content_handlers=[ElementContentHandler("div", element=add_class)]
+    )
+    documents = [make_document(i) for i in range(16)]
+
+    with ThreadPoolExecutor(max_workers=4) as pool:
+        results = list(pool.map(rewriter.rewrite, documents))
+
+    assert results == [
+        document.replace(f"<div id={i}>", f'<div id={i} class="x">')
+        for i, document in enumerate(documents)
+    ]
+
+
+def test_errors_without_handlers():
+    with pytest.raises(ParsingAmbiguityError):
+        rewrite_str("<select><xmp><script>'</select>")
+
+
+def test_gil_is_released_while_rewriting():
+    rewriter = Rewriter(element_content_handlers=[SetAttribute("p", "class", "x")])
+    document = make_document(0)
+
+    # NOTE: the document is grown until its rewrite is long enough to observe other threads.
+    while True:
+        start = time.perf_counter()
+        rewriter.rewrite(document)
+
+        if time.perf_counter() - start > 0.2:
+            break
+
+        document *= 2
+
+    span = []
+    ticks = []
+
+    def rewrite():
+        span.append(time.perf_counter())
+        rewriter.rewrite(document)
+        span.append(time.perf_counter())
+
+    thread = threading.Thread(target=rewrite)
+    thread.start()
+
+    while thread.is_alive():
+        ticks.append(time.perf_counter())
+        time.sleep(0.001)
+
+    thread.join()
+
+    # NOTE: the margin leaves out the ticks made before the rewriting has started.
+    started, finished = span[0] + 0.05, span[1]
+
+    assert len([tick for tick in ticks if started < tick < finished]) >= 10